@@ -0,0 +1,71 @@
+//! Antichess, also known as losing chess. Captures are compulsory, the king is an ordinary piece
+//! without royal powers, and a side wins by losing all its pieces or by being stalemated.
+
+use crate::chess_board::ChessBoard;
+use crate::chess_move::ChessMove;
+use crate::types::PieceType::*;
+use crate::variant::{Variant, VariantBoard};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color, GameResult};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Antichess;
+
+pub type AntichessBoard = VariantBoard<Antichess>;
+
+impl Variant for Antichess {
+    type Undo = ();
+
+    /// The standard starting position, without castling rights
+    const START_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1";
+
+    const COMPULSORY_CAPTURES: bool = true;
+
+    fn king_safety(_board: &VariantBoard<Self>, _color: Color) -> bool {
+        false
+    }
+
+    /// Adds the promotions to king
+    fn generate_extra_moves(_board: &VariantBoard<Self>, moves: &mut Vec<ChessMove>) {
+        let king_promotions = moves
+            .iter()
+            .filter(|mv| mv.prom == Some(Queen))
+            .map(|mv| ChessMove::new_prom(mv.from, mv.to, King))
+            .collect::<Vec<_>>();
+        moves.extend(king_promotions);
+    }
+
+    fn is_in_check(_board: &VariantBoard<Self>) -> bool {
+        false
+    }
+
+    /// The side to move wins if it has no pieces left or no legal moves
+    fn no_moves_result(board: &VariantBoard<Self>) -> GameResult {
+        match board.side_to_move() {
+            White => GameResult::WhiteWin,
+            Black => GameResult::BlackWin,
+        }
+    }
+
+    /// Parses a FEN string. Castling is not allowed in Antichess, so the castling field must be `-`
+    fn from_fen(fen: &str) -> Result<VariantBoard<Self>, pgn::Error> {
+        let board = ChessBoard::from_fen(fen)?;
+        if [White, Black]
+            .iter()
+            .any(|&color| board.can_castle_kingside(color) || board.can_castle_queenside(color))
+        {
+            return Err(pgn::Error::new(
+                pgn::ErrorKind::IllegalPosition,
+                format!("Antichess FEN {} has castling rights", fen),
+            ));
+        }
+        Ok(VariantBoard::new(board, Antichess))
+    }
+
+    /// There is no check in Antichess, so the only suffix is `#` for a move that wins for the opponent
+    fn san_suffix(board: &VariantBoard<Self>) -> Option<char> {
+        board.game_result().map(|_| '#')
+    }
+}
@@ -0,0 +1,182 @@
+//! Atomic chess, where every capture sets off an explosion that removes the capturing piece,
+//! the captured piece, and every other piece next to the capture square except pawns.
+//! Exploding the enemy king wins the game.
+
+use crate::attacks;
+use crate::chess_board::{Castling, ChessBoard};
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use crate::move_gen;
+use crate::types::{Piece, PieceType::*, Square};
+use crate::variant::{Variant, VariantBoard, VariantReverseMove};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color, GameResult};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Atomic;
+
+pub type AtomicBoard = VariantBoard<Atomic>;
+
+pub type AtomicReverseMove = VariantReverseMove<Explosion>;
+
+/// The pieces removed by an explosion, and the squares they stood on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Explosion {
+    exploded: [(Square, Piece); 9],
+    num_exploded: usize,
+}
+
+impl Default for Explosion {
+    fn default() -> Self {
+        Explosion {
+            exploded: [(Square(0), Piece::empty()); 9],
+            num_exploded: 0,
+        }
+    }
+}
+
+/// Returns whether `color`'s king is attacked, unless the kings are next to each other
+fn king_is_attacked(board: &ChessBoard, color: Color) -> bool {
+    let king_square = match board.piece_bitboard(King, color).first_square() {
+        Some(square) => square,
+        None => return false,
+    };
+    let enemy_king = board.piece_bitboard(King, !color);
+    if !(attacks::king_attacks(king_square) & enemy_king).is_empty() {
+        return false;
+    }
+    !board.attackers_to(king_square, !color).is_empty()
+}
+
+/// Returns whether the move, assumed to follow the movement rules of the pieces,
+/// keeps the mover's king on the board and out of check.
+/// `scratch` must be a copy of `board`, and is restored before returning.
+fn is_legal(board: &AtomicBoard, scratch: &mut AtomicBoard, mv: ChessMove) -> bool {
+    let color = board.side_to_move();
+    if board.board[mv.from].piece_type() == King && board.is_capture(mv) {
+        // The king would explode with the piece it captures
+        return false;
+    }
+    let reverse_move = scratch.do_move(mv);
+    let is_legal = if scratch.board.piece_bitboard(King, color).is_empty() {
+        false
+    } else if scratch.board.piece_bitboard(King, !color).is_empty() {
+        true
+    } else {
+        !king_is_attacked(&scratch.board, color)
+    };
+    scratch.reverse_move(reverse_move);
+    is_legal
+}
+
+/// Returns whether the side to move may castle with the castling move.
+/// The king may not castle out of, through or into check,
+/// but squares next to the enemy king are safe.
+fn castling_is_legal(board: &AtomicBoard, castling: Castling) -> bool {
+    let color = board.side_to_move();
+    let mut board = board.board.clone();
+    board.set_piece(castling.king_from, Piece::empty());
+    board.set_piece(castling.rook_from, Piece::empty());
+    move_gen::castling_king_path(castling)
+        .squares()
+        .all(|square| {
+            board.set_piece(square, Piece::from_type_color(King, color));
+            let is_attacked = king_is_attacked(&board, color);
+            board.set_piece(square, Piece::empty());
+            !is_attacked
+        })
+}
+
+impl Variant for Atomic {
+    type Undo = Explosion;
+
+    fn king_safety(_board: &VariantBoard<Self>, _color: Color) -> bool {
+        false
+    }
+
+    fn generate_castling(board: &VariantBoard<Self>, moves: &mut Vec<ChessMove>) {
+        for &kingside in &[true, false] {
+            if let Some((mv, castling)) = move_gen::unobstructed_castling(&board.board, kingside) {
+                if castling_is_legal(board, castling) {
+                    moves.push(mv);
+                }
+            }
+        }
+    }
+
+    /// Removes the moves that explode the mover's own king or leave it in check.
+    /// Castling moves have already been checked.
+    fn retain_legal(board: &VariantBoard<Self>, moves: &mut Vec<ChessMove>) {
+        let mut scratch = board.clone();
+        moves.retain(|&mv| board.board.is_castling(mv) || is_legal(board, &mut scratch, mv));
+    }
+
+    /// Returns whether the side to move's king is attacked.
+    /// A king next to the enemy king is never in check, because capturing it would explode both kings.
+    fn is_in_check(board: &VariantBoard<Self>) -> bool {
+        king_is_attacked(&board.board, board.side_to_move())
+    }
+
+    /// The side that explodes the enemy king wins
+    fn variant_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        if board.board.piece_bitboard(King, Black).is_empty() {
+            Some(GameResult::WhiteWin)
+        } else if board.board.piece_bitboard(King, White).is_empty() {
+            Some(GameResult::BlackWin)
+        } else {
+            None
+        }
+    }
+
+    fn do_move(board: &mut VariantBoard<Self>, mv: ChessMove) -> (ChessReverseMove, Self::Undo) {
+        let is_capture = board.is_capture(mv);
+        let reverse_move = board.board.do_move(mv);
+        let mut explosion = Explosion::default();
+
+        if is_capture {
+            let board = &mut board.board;
+            let blast = (attacks::king_attacks(mv.to) & !board.piece_type_bitboard(Pawn))
+                .set(mv.to)
+                & board.occupied();
+            for square in blast {
+                explosion.exploded[explosion.num_exploded] = (square, board[square]);
+                explosion.num_exploded += 1;
+                board.set_piece(square, Piece::empty());
+            }
+            // Exploded kings and rooks take their castling rights with them
+            for &color in &[White, Black] {
+                if board.piece_bitboard(King, color).is_empty() {
+                    board.disable_castling(color);
+                }
+                let own_rook = Piece::from_type_color(Rook, color);
+                if board[board.castling_rook_square(color, true)] != own_rook {
+                    board.disable_castling_kingside(color);
+                }
+                if board[board.castling_rook_square(color, false)] != own_rook {
+                    board.disable_castling_queenside(color);
+                }
+            }
+        }
+
+        (reverse_move, explosion)
+    }
+
+    fn reverse_move(
+        board: &mut VariantBoard<Self>,
+        reverse_move: ChessReverseMove,
+        undo: Self::Undo,
+    ) {
+        for &(square, piece) in undo.exploded[..undo.num_exploded].iter() {
+            board.board.set_piece(square, piece);
+        }
+        board.board.reverse_move(reverse_move);
+    }
+
+    /// Returns `#` for a move that wins, by checkmate or by exploding the king, and `+` for a check
+    fn san_suffix(board: &VariantBoard<Self>) -> Option<char> {
+        match board.game_result() {
+            Some(GameResult::WhiteWin) | Some(GameResult::BlackWin) => Some('#'),
+            _ if board.is_in_check() => Some('+'),
+            _ => None,
+        }
+    }
+}
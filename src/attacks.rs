@@ -0,0 +1,195 @@
+//! Attack sets for every piece type, as bitboards.
+//!
+//! Knight, king and pawn attacks are precomputed at compile time.
+//! Sliding piece attacks are looked up in the tables from the `magic` module.
+
+use crate::bitboard::BitBoard;
+use crate::magic;
+use crate::types::Square;
+use board_game_traits::board::Color;
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+pub(crate) const ROOK_DIRECTIONS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+pub(crate) const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+const fn offset_table(offsets: &[(i8, i8); 8]) -> [BitBoard; 64] {
+    let mut table = [BitBoard::empty(); 64];
+    let mut square = 0;
+    while square < 64 {
+        let file = (square % 8) as i8;
+        let rank = (square / 8) as i8;
+        let mut i = 0;
+        while i < 8 {
+            let (file_offset, rank_offset) = offsets[i];
+            let (new_file, new_rank) = (file + file_offset, rank + rank_offset);
+            if new_file >= 0 && new_file < 8 && new_rank >= 0 && new_rank < 8 {
+                table[square] = table[square].set(Square((new_rank * 8 + new_file) as u8));
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+const fn pawn_table(rank_offset: i8) -> [BitBoard; 64] {
+    let mut table = [BitBoard::empty(); 64];
+    let mut square = 0;
+    while square < 64 {
+        let file = (square % 8) as i8;
+        let new_rank = (square / 8) as i8 + rank_offset;
+        if new_rank >= 0 && new_rank < 8 {
+            if file > 0 {
+                table[square] = table[square].set(Square((new_rank * 8 + file - 1) as u8));
+            }
+            if file < 7 {
+                table[square] = table[square].set(Square((new_rank * 8 + file + 1) as u8));
+            }
+        }
+        square += 1;
+    }
+    table
+}
+
+/// Builds the `BETWEEN` table when `full_line` is false, and the `LINE` table otherwise
+const fn line_table(full_line: bool) -> [[BitBoard; 64]; 64] {
+    let mut table = [[BitBoard::empty(); 64]; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut direction = 0;
+        while direction < 8 {
+            let (i, j) = if direction < 4 {
+                ROOK_DIRECTIONS[direction]
+            } else {
+                BISHOP_DIRECTIONS[direction - 4]
+            };
+            let full_ray = ray(square as i8, i, j).board | ray(square as i8, -i, -j).board;
+
+            let mut file = (square % 8) as i8 + i;
+            let mut rank = (square / 8) as i8 + j;
+            let mut passed_squares = 0;
+            while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                let target = (rank * 8 + file) as usize;
+                table[square][target] = if full_line {
+                    BitBoard::from_u64(full_ray | 1 << square)
+                } else {
+                    BitBoard::from_u64(passed_squares)
+                };
+                passed_squares |= 1 << target;
+                file += i;
+                rank += j;
+            }
+            direction += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+/// Every square from `square` in the given direction, until the edge of the board
+const fn ray(square: i8, i: i8, j: i8) -> BitBoard {
+    let mut ray = 0;
+    let mut file = square % 8 + i;
+    let mut rank = square / 8 + j;
+    while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+        ray |= 1 << (rank * 8 + file);
+        file += i;
+        rank += j;
+    }
+    BitBoard::from_u64(ray)
+}
+
+static KNIGHT_ATTACKS: [BitBoard; 64] = offset_table(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [BitBoard; 64] = offset_table(&KING_OFFSETS);
+static PAWN_ATTACKS: [[BitBoard; 64]; 2] = [pawn_table(-1), pawn_table(1)];
+static BETWEEN: [[BitBoard; 64]; 64] = line_table(false);
+static LINE: [[BitBoard; 64]; 64] = line_table(true);
+
+pub fn knight_attacks(square: Square) -> BitBoard {
+    KNIGHT_ATTACKS[square.0 as usize]
+}
+
+pub fn king_attacks(square: Square) -> BitBoard {
+    KING_ATTACKS[square.0 as usize]
+}
+
+/// Returns the squares attacked by a pawn of the given color on `square`
+pub fn pawn_attacks(color: Color, square: Square) -> BitBoard {
+    PAWN_ATTACKS[color.disc()][square.0 as usize]
+}
+
+/// Returns the squares strictly between two squares on the same rank, file or diagonal.
+/// Returns an empty set if the squares are not aligned.
+pub fn between(from: Square, to: Square) -> BitBoard {
+    BETWEEN[from.0 as usize][to.0 as usize]
+}
+
+/// Returns the entire rank, file or diagonal going through both squares, from edge to edge.
+/// Returns an empty set if the squares are not aligned.
+pub fn line(from: Square, to: Square) -> BitBoard {
+    LINE[from.0 as usize][to.0 as usize]
+}
+
+/// Returns the squares a rook on `square` attacks, given the occupied squares on the board.
+/// The first blocker in each direction is included, regardless of its color.
+pub fn rook_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    magic::rook_attacks(square, occupied)
+}
+
+/// Returns the squares a bishop on `square` attacks, given the occupied squares on the board.
+/// The first blocker in each direction is included, regardless of its color.
+pub fn bishop_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    magic::bishop_attacks(square, occupied)
+}
+
+pub fn queen_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+/// Walks each ray from `square` until it leaves the board or hits a piece.
+/// Slow, but used to build the magic lookup tables.
+pub(crate) fn sliding_attacks(
+    square: Square,
+    occupied: BitBoard,
+    directions: &[(i8, i8)],
+) -> BitBoard {
+    let mut attacks = BitBoard::empty();
+    for &(i, j) in directions {
+        let mut file = square.file() as i8;
+        let mut rank = square.rank() as i8;
+        loop {
+            file += i;
+            rank += j;
+            if !(0..8).contains(&file) || !(0..8).contains(&rank) {
+                break;
+            }
+            let target = Square::from_ints(file as u8, rank as u8);
+            attacks = attacks.set(target);
+            if occupied.get(target) {
+                break;
+            }
+        }
+    }
+    attacks
+}
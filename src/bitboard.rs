@@ -0,0 +1,192 @@
+use crate::types::Square;
+use std::fmt;
+use std::ops;
+
+/// A set of squares, stored as one bit per square.
+/// Bit `n` corresponds to `Square(n)`, so the least significant bit is a8 and the most significant bit is h1.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BitBoard {
+    pub board: u64,
+}
+
+impl ops::BitAnd for BitBoard {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        BitBoard::from_u64(self.board & rhs.board)
+    }
+}
+
+impl ops::BitOr for BitBoard {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        BitBoard::from_u64(self.board | rhs.board)
+    }
+}
+
+impl ops::BitXor for BitBoard {
+    type Output = Self;
+    fn bitxor(self, rhs: Self) -> Self {
+        BitBoard::from_u64(self.board ^ rhs.board)
+    }
+}
+
+impl ops::Not for BitBoard {
+    type Output = Self;
+    fn not(self) -> Self {
+        BitBoard::from_u64(!self.board)
+    }
+}
+
+impl ops::BitAndAssign for BitBoard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.board &= rhs.board;
+    }
+}
+
+impl ops::BitOrAssign for BitBoard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.board |= rhs.board;
+    }
+}
+
+impl ops::BitXorAssign for BitBoard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        self.board ^= rhs.board;
+    }
+}
+
+impl fmt::Debug for BitBoard {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        writeln!(fmt)?;
+        for rank in 0..8 {
+            for file in 0..8 {
+                if self.get(Square::from_ints(file, rank)) {
+                    write!(fmt, "[x]")?;
+                } else {
+                    write!(fmt, "[ ]")?;
+                }
+            }
+            writeln!(fmt)?;
+        }
+        Ok(())
+    }
+}
+
+impl BitBoard {
+    pub const fn empty() -> Self {
+        BitBoard { board: 0 }
+    }
+
+    pub const fn full() -> Self {
+        BitBoard { board: u64::MAX }
+    }
+
+    pub const fn from_u64(board: u64) -> Self {
+        BitBoard { board }
+    }
+
+    pub const fn from_square(square: Square) -> Self {
+        BitBoard {
+            board: 1 << square.0,
+        }
+    }
+
+    /// Returns the set of all squares on a rank, where rank 0 is the 8th rank.
+    pub const fn rank(rank: u8) -> Self {
+        BitBoard {
+            board: 0xff << (rank * 8),
+        }
+    }
+
+    /// Returns the set of all squares on a file, where file 0 is the a-file.
+    pub const fn file(file: u8) -> Self {
+        BitBoard {
+            board: 0x0101_0101_0101_0101 << file,
+        }
+    }
+
+    /// Returns the set of light squares, such as a8 and h1
+    pub const fn light_squares() -> Self {
+        BitBoard {
+            board: 0xaa55_aa55_aa55_aa55,
+        }
+    }
+
+    /// Returns the set of dark squares, such as a1 and h8
+    pub const fn dark_squares() -> Self {
+        BitBoard {
+            board: !0xaa55_aa55_aa55_aa55,
+        }
+    }
+
+    pub const fn get(self, square: Square) -> bool {
+        self.board & (1 << square.0) != 0
+    }
+
+    pub const fn set(self, square: Square) -> Self {
+        BitBoard {
+            board: self.board | (1 << square.0),
+        }
+    }
+
+    pub const fn clear(self, square: Square) -> Self {
+        BitBoard {
+            board: self.board & !(1 << square.0),
+        }
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.board == 0
+    }
+
+    pub const fn popcount(self) -> u32 {
+        self.board.count_ones()
+    }
+
+    /// Returns the square with the lowest index in the set, if any
+    pub fn first_square(self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Square(self.board.trailing_zeros() as u8))
+        }
+    }
+
+    /// Returns whether the set has more than one square
+    pub const fn has_several(self) -> bool {
+        self.board & self.board.wrapping_sub(1) != 0
+    }
+
+    pub fn squares(self) -> SquareIter {
+        SquareIter { bitboard: self }
+    }
+}
+
+impl IntoIterator for BitBoard {
+    type Item = Square;
+    type IntoIter = SquareIter;
+    fn into_iter(self) -> Self::IntoIter {
+        self.squares()
+    }
+}
+
+/// Iterates over the squares in a bitboard, from a8 to h1
+pub struct SquareIter {
+    bitboard: BitBoard,
+}
+
+impl Iterator for SquareIter {
+    type Item = Square;
+    fn next(&mut self) -> Option<Square> {
+        let square = self.bitboard.first_square()?;
+        self.bitboard.board &= self.bitboard.board - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.bitboard.popcount() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for SquareIter {}
@@ -1,6 +1,9 @@
+use crate::bitboard::BitBoard;
 use crate::chess_move::{ChessMove, ChessReverseMove};
 use crate::move_gen;
+use crate::move_list::MoveList;
 use crate::types::{Piece, PieceType, PieceType::*, Square};
+use crate::zobrist;
 use board_game_traits::board;
 use board_game_traits::board::Color;
 use board_game_traits::board::Color::*;
@@ -11,25 +14,90 @@ use pgn_traits::pgn::PgnBoard;
 use crate::chess_move::ChessReverseNullMove;
 use board_game_traits::board::Board;
 use board_game_traits::board::ExtendedBoard;
+use std::collections::HashSet;
 use std::fmt;
 use std::fmt::Write;
 use std::hash::{Hash, Hasher};
 use std::ops;
 
+/// The files of the kingside and queenside rooks in standard chess, for each color
+const STANDARD_CASTLING_ROOK_FILES: [[u8; 2]; 2] = [[7, 0], [7, 0]];
+
+/// The squares the king and rook move between when castling
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Castling {
+    pub king_from: Square,
+    pub king_to: Square,
+    pub rook_from: Square,
+    pub rook_to: Square,
+}
+
+impl Castling {
+    pub fn is_kingside(self) -> bool {
+        self.rook_from.file() > self.king_from.file()
+    }
+}
+
+/// The reason a game has ended, or may be ended by a claim
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate {
+        winner: Color,
+    },
+    Stalemate,
+    /// Neither side has the material to checkmate. The game is drawn automatically
+    InsufficientMaterial,
+    /// 50 moves by each side without a capture or pawn move. Either player may claim a draw
+    FiftyMoveRule,
+    /// 75 moves by each side without a capture or pawn move. The game is drawn automatically
+    SeventyFiveMoveRule,
+    /// The position has occurred three times. Either player may claim a draw
+    ThreefoldRepetition,
+    /// The position has occurred five times. The game is drawn automatically
+    FivefoldRepetition,
+}
+
+impl Termination {
+    pub fn result(self) -> board::GameResult {
+        match self {
+            Termination::Checkmate { winner: White } => board::GameResult::WhiteWin,
+            Termination::Checkmate { winner: Black } => board::GameResult::BlackWin,
+            _ => board::GameResult::Draw,
+        }
+    }
+
+    /// Returns whether the game only ends if a player claims the draw
+    pub fn is_claimable(self) -> bool {
+        matches!(
+            self,
+            Termination::FiftyMoveRule | Termination::ThreefoldRepetition
+        )
+    }
+}
+
+/// A chess position, whose bitboards and Zobrist key stay in sync as pieces are placed with `set_piece`
 #[derive(Clone)]
 pub struct ChessBoard {
-    pub board: [[Piece; 8]; 8],
-    pub to_move: Color,
-    pub castling_en_passant: u8,
+    board: [[Piece; 8]; 8],
+    // Indexed by `PieceType`. The `Empty` entry holds all empty squares
+    piece_type_bitboards: [BitBoard; 7],
+    color_bitboards: [BitBoard; 2],
+    pub(crate) to_move: Color,
+    pub(crate) castling_en_passant: u8,
     pub half_move_clock: u8,
     pub move_num: u16,
+    hash: u64,
+    // Keys of the earlier positions in the game, if enabled. Used to detect repetitions
+    history: Option<Vec<u64>>,
+    // Whether castling is encoded as the king capturing its own rook
+    chess960: bool,
+    // The files of the kingside and queenside castling rooks, for each color
+    castling_rook_files: [[u8; 2]; 2],
 }
 
 impl Hash for ChessBoard {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.board.hash(state);
-        self.to_move.hash(state);
-        self.castling_en_passant.hash(state);
+        self.hash.hash(state);
     }
 }
 
@@ -38,6 +106,19 @@ impl PartialEq for ChessBoard {
         self.board == other.board
             && self.to_move == other.to_move
             && self.castling_en_passant == other.castling_en_passant
+            // A rook file only matters while the side can still castle with that rook
+            && [White, Black].iter().all(|&color| {
+                [true, false].iter().all(|&kingside| {
+                    let can_castle = if kingside {
+                        self.can_castle_kingside(color)
+                    } else {
+                        self.can_castle_queenside(color)
+                    };
+                    !can_castle
+                        || self.castling_rook_file(color, kingside)
+                            == other.castling_rook_file(color, kingside)
+                })
+            })
     }
 }
 
@@ -53,6 +134,12 @@ impl BoardIter {
     }
 }
 
+impl Default for BoardIter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Iterator for BoardIter {
     type Item = Square;
     fn next(&mut self) -> Option<Square> {
@@ -143,38 +230,127 @@ fn parse_fen_to_move(to_move: &str) -> Result<Color, pgn::Error> {
     }
 }
 
+/// Parses the castling field of a FEN string. Accepts the standard `KQkq` letters,
+/// and the file letters of Shredder-FEN and X-FEN, which name the castling rook's file.
+/// `K` and `Q` refer to the outermost rook on that side of the king.
 fn parse_fen_castling_rights(castling_str: &str, board: &mut ChessBoard) -> Result<(), pgn::Error> {
-    let mut castling_rights = [false; 4];
-    for c in castling_str.chars() {
-        match c {
-            '-' => break,
-            'K' => castling_rights[0] = true,
-            'Q' => castling_rights[1] = true,
-            'k' => castling_rights[2] = true,
-            'q' => castling_rights[3] = true,
-            _ => {
-                return Err(pgn::Error::new(
-                    pgn::ErrorKind::ParseError,
-                    "Invalid FEN string: Error in castling field.".to_string(),
-                ))
+    // The file of each castling rook, indexed by color, and then kingside first
+    let mut rook_files: [[Option<u8>; 2]; 2] = [[None; 2]; 2];
+    if castling_str != "-" {
+        for c in castling_str.chars() {
+            let color = if c.is_ascii_uppercase() { White } else { Black };
+            let back_rank = BitBoard::rank(if color == White { 7 } else { 0 });
+            let king_file = match (board.piece_bitboard(King, color) & back_rank).first_square() {
+                Some(square) => square.file(),
+                None => {
+                    return Err(pgn::Error::new(
+                        pgn::ErrorKind::IllegalPosition,
+                        format!(
+                            "FEN string has castling rights for {}, but its king is not on its back rank",
+                            color
+                        ),
+                    ))
+                }
+            };
+            let mut rook_files_on_rank = (board.piece_bitboard(Rook, color) & back_rank)
+                .squares()
+                .map(Square::file);
+            let rook_file = match c.to_ascii_lowercase() {
+                'k' => rook_files_on_rank.filter(|&file| file > king_file).last(),
+                'q' => rook_files_on_rank.find(|&file| file < king_file),
+                file @ 'a'..='h' => {
+                    rook_files_on_rank.find(|&rook_file| rook_file == file as u8 - b'a')
+                }
+                _ => {
+                    return Err(pgn::Error::new(
+                        pgn::ErrorKind::ParseError,
+                        "Invalid FEN string: Error in castling field.".to_string(),
+                    ))
+                }
+            };
+            match rook_file {
+                Some(file) => {
+                    let side = if file > king_file { 0 } else { 1 };
+                    rook_files[color.disc()][side] = Some(file);
+                }
+                None => {
+                    return Err(pgn::Error::new(
+                        pgn::ErrorKind::IllegalPosition,
+                        format!(
+                            "FEN string has castling right {}, but there is no rook to castle with",
+                            c
+                        ),
+                    ))
+                }
             }
         }
     }
-    if !castling_rights[0] {
-        board.disable_castling_kingside(White)
-    }
-    if !castling_rights[1] {
-        board.disable_castling_queenside(White)
-    }
-    if !castling_rights[2] {
-        board.disable_castling_kingside(Black)
+    for &color in &[White, Black] {
+        if rook_files[color.disc()][0].is_none() {
+            board.disable_castling_kingside(color)
+        }
+        if rook_files[color.disc()][1].is_none() {
+            board.disable_castling_queenside(color)
+        }
     }
-    if !castling_rights[3] {
-        board.disable_castling_queenside(Black)
+    for color in 0..2 {
+        for side in 0..2 {
+            board.castling_rook_files[color][side] =
+                rook_files[color][side].unwrap_or(STANDARD_CASTLING_ROOK_FILES[color][side]);
+        }
     }
+
+    // Castling is only encoded as the king moving two squares from the e-file, with rooks in the corners
+    let kings_on_e_file = [White, Black].iter().all(|&color| {
+        !(board.can_castle_kingside(color) || board.can_castle_queenside(color))
+            || board.king_pos(color).file() == 4
+    });
+    board.chess960 = board.castling_rook_files != STANDARD_CASTLING_ROOK_FILES || !kings_on_e_file;
     Ok(())
 }
 
+/// Returns the pieces on the back rank of a Chess960 start position, from the a-file to the h-file
+fn chess960_back_rank(index: u16) -> [PieceType; 8] {
+    // The squares of the two knights, among the five squares left after placing the bishops and queen
+    const KNIGHT_SQUARES: [(usize, usize); 10] = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+    let mut back_rank = [Empty; 8];
+    let mut n = index as usize;
+    back_rank[n % 4 * 2 + 1] = Bishop;
+    n /= 4;
+    back_rank[n % 4 * 2] = Bishop;
+    n /= 4;
+
+    let empty_files = |back_rank: &[PieceType; 8]| {
+        (0..8)
+            .filter(|&file| back_rank[file] == Empty)
+            .collect::<Vec<_>>()
+    };
+    back_rank[empty_files(&back_rank)[n % 6]] = Queen;
+    n /= 6;
+
+    let (first_knight, second_knight) = KNIGHT_SQUARES[n];
+    let files = empty_files(&back_rank);
+    back_rank[files[first_knight]] = Knight;
+    back_rank[files[second_knight]] = Knight;
+
+    // The king goes between the rooks on the three remaining squares
+    for (&file, &piece_type) in empty_files(&back_rank).iter().zip(&[Rook, King, Rook]) {
+        back_rank[file] = piece_type;
+    }
+    back_rank
+}
+
 impl PgnBoard for ChessBoard {
     fn from_fen(fen: &str) -> Result<Self, pgn::Error> {
         let fen_split: Vec<&str> = fen.split(' ').collect();
@@ -189,14 +365,13 @@ impl PgnBoard for ChessBoard {
             ));
         }
 
-        let mut board = ChessBoard {
-            board: [[Piece::empty(); 8]; 8],
-            to_move: White,
-            castling_en_passant: 15,
-            half_move_clock: 0,
-            move_num: 0,
-        };
-        board.board = parse_fen_board(fen_split[0])?;
+        let mut board = ChessBoard::empty();
+        board.set_castling_en_passant(15);
+        for (rank, pieces) in parse_fen_board(fen_split[0])?.iter().enumerate() {
+            for (file, &piece) in pieces.iter().enumerate() {
+                board.set_piece(Square::from_ints(file as u8, rank as u8), piece);
+            }
+        }
 
         if fen_split[1].len() != 1 {
             return Err(pgn::Error::new(
@@ -206,7 +381,9 @@ impl PgnBoard for ChessBoard {
         }
 
         // Check side to move
-        board.to_move = parse_fen_to_move(fen_split[1])?;
+        if parse_fen_to_move(fen_split[1])? == Black {
+            board.flip_side_to_move();
+        }
 
         // Check castling rights field
         parse_fen_castling_rights(fen_split[2], &mut board)?;
@@ -245,24 +422,6 @@ impl PgnBoard for ChessBoard {
         board.half_move_clock = half_clock;
         board.move_num = move_num;
 
-        if (board.can_castle_kingside(White) || board.can_castle_queenside(White))
-            && board[Square::E1] != Piece::from_type_color(King, White)
-        {
-            return Err(pgn::Error::new(
-                pgn::ErrorKind::IllegalPosition,
-                "FEN string has white castling rights, but white's king is not on e1",
-            ));
-        }
-
-        if (board.can_castle_kingside(Black) || board.can_castle_queenside(Black))
-            && board[Square::E8] != Piece::from_type_color(King, Black)
-        {
-            return Err(pgn::Error::new(
-                pgn::ErrorKind::IllegalPosition,
-                "FEN string has black castling rights, but black's king is not on e8",
-            ));
-        }
-
         Ok(board)
     }
     fn to_fen(&self) -> String {
@@ -294,21 +453,7 @@ impl PgnBoard for ChessBoard {
         }
 
         string.push(' ');
-        if self.can_castle_kingside(White) {
-            string.push('K');
-        }
-        if self.can_castle_queenside(White) {
-            string.push('Q');
-        }
-        if self.can_castle_kingside(Black) {
-            string.push('k');
-        }
-        if self.can_castle_queenside(Black) {
-            string.push('q');
-        }
-        if string.ends_with(' ') {
-            string.push('-');
-        }
+        string.push_str(&self.fen_castling_field(false));
 
         match self.en_passant_square() {
             Some(square) => {
@@ -323,225 +468,35 @@ impl PgnBoard for ChessBoard {
     }
 
     fn move_to_san(&self, mv: &<Self as Board>::Move) -> String {
-        let color = self.side_to_move();
-        let piece_type = self.piece_at(mv.from).piece_type();
-
-        let mut output = String::new();
-
-        if piece_type == King && mv.from.file() == 4 && mv.to.file() == 2 {
-            output.push_str("0-0-0");
-        } else if piece_type == King && mv.from.file() == 4 && mv.to.file() == 6 {
-            output.push_str("0-0");
-        } else {
-            if piece_type != Pawn {
-                output.push(piece_type.letter());
-            }
-
-            let mut moves = vec![];
-            self.generate_moves(&mut moves);
-
-            let alternative_from_squares = moves
-                .iter()
-                .filter(|&cand_mv| cand_mv.from != mv.from)
-                .filter(|&cand_mv| {
-                    cand_mv.to == mv.to && self.piece_at(cand_mv.from) == self.piece_at(mv.from)
-                })
-                .map(|cand_mv| cand_mv.from)
-                .collect::<Vec<_>>();
-
-            if alternative_from_squares.is_empty()
-                && (piece_type != Pawn || mv.from.file() == mv.to.file())
-            {
-            }
-            // Disambiguate with departure file
-            else if alternative_from_squares
-                .iter()
-                .all(|square| square.file() != mv.from.file())
-            {
-                output.push(mv.from.to_string().chars().next().unwrap());
-            }
-            // Disambiguate with departure rank
-            else if alternative_from_squares
-                .iter()
-                .all(|square| square.rank() != mv.from.rank())
-            {
-                output.push(mv.from.to_string().chars().last().unwrap());
-            }
-            // Disambiguate with full square
-            else {
-                output.push_str(&mv.from.to_string());
-            };
-
-            if self.piece_at(mv.to) != Piece::Empty {
-                output.push('x');
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        let mut output = self.move_to_san_in(mv, &moves);
+
+        if self.gives_check(*mv) {
+            let mut cloned_board = self.clone();
+            cloned_board.do_move(*mv);
+            // Only checkmate is written as mate. A check may also end the game in a draw,
+            // such as by insufficient material or the move counter
+            if !move_gen::has_legal_moves(&cloned_board) {
+                output.push('#');
+            } else {
+                output.push('+');
             }
-
-            output.push_str(&mv.to.to_string());
-
-            match mv.prom {
-                Some(Queen) => output.push_str("=Q"),
-                Some(Rook) => output.push_str("=R"),
-                Some(Knight) => output.push_str("=N"),
-                Some(Bishop) => output.push_str("=B"),
-                None => (),
-                _ => panic!("Illegal promotion move"),
-            };
-        }
-
-        let mut cloned_board = self.clone();
-        cloned_board.do_move(mv.clone());
-        if cloned_board.game_result() == Some(board::GameResult::WhiteWin)
-            || cloned_board.game_result() == Some(board::GameResult::BlackWin)
-        {
-            output.push('#');
-        } else if move_gen::is_attacked(&cloned_board, cloned_board.king_pos(!color)) {
-            output.push('+');
         }
 
         output
     }
 
     fn move_from_san(&self, input: &str) -> Result<<Self as Board>::Move, pgn::Error> {
-        if input == "0-0-0" || input == "0-0-0+" || input == "0-0-0#" {
-            match self.side_to_move() {
-                White => return self.move_from_lan("e1c1"),
-                Black => return self.move_from_lan("e8c8"),
-            }
-        } else if input == "0-0" || input == "0-0+" || input == "0-0#" {
-            match self.side_to_move() {
-                White => return self.move_from_lan("e1g1"),
-                Black => return self.move_from_lan("e8g8"),
-            }
-        }
-
-        if input.chars().count() < 2 {
-            return Err(pgn::Error::new(
-                pgn::ErrorKind::ParseError,
-                format!(
-                    "Invalid move {}: Too short at length {:?}",
-                    input,
-                    input.chars().count()
-                ),
-            ));
-        }
-
-        let piece_type =
-            PieceType::from_letter(input.chars().next().unwrap()).unwrap_or(PieceType::Pawn);
-
-        let mut reverse_chars = input.chars().rev().peekable();
-
-        if reverse_chars.peek() == Some(&'+') || reverse_chars.peek() == Some(&'#') {
-            reverse_chars.next();
-        }
-
-        let promotion = reverse_chars
-            .peek()
-            .cloned()
-            .and_then(PieceType::from_letter);
-        if promotion.is_some() {
-            reverse_chars.next();
-            if reverse_chars.next() != Some('=') {
-                return Err(pgn::Error::new(
-                    pgn::ErrorKind::ParseError,
-                    format!("Illegal promotion on {}", input),
-                ));
-            }
-        }
-
-        let dest_square = Square::from_alg(
-            &[reverse_chars.next(), reverse_chars.next()]
-                .iter()
-                .rev()
-                .filter_map(|&ch| ch)
-                .collect::<String>(),
-        )
-        .map_err(|err| {
-            pgn::Error::new_caused_by(
-                pgn::ErrorKind::ParseError,
-                format!("Illegal move {}", input),
-                err,
-            )
-        })?;
-
-        if reverse_chars.peek() == Some(&'x') {
-            reverse_chars.next();
-        }
-
-        let mut disambig_string = reverse_chars
-            .take_while(|&ch| PieceType::from_letter(ch).is_none())
-            .collect::<String>();
-        disambig_string = disambig_string.chars().rev().collect();
-
-        let move_filter: Box<Fn(&ChessMove) -> bool> = match (
-            disambig_string.chars().count(),
-            disambig_string.chars().next(),
-        ) {
-            (0, None) => Box::new(|_| true),
-
-            (1, Some(rank)) if rank >= '1' && rank <= '8' => {
-                Box::new(move |mv: &ChessMove| mv.from.rank() == 7 - (rank as u8 - b'1'))
-            }
-
-            (1, Some(file)) if file >= 'a' && file <= 'h' => {
-                Box::new(move |mv: &ChessMove| mv.from.file() == (file as u8 - b'a'))
-            }
-
-            (2, _) if Square::from_alg(&disambig_string).is_ok() => {
-                Box::new(move |mv: &ChessMove| {
-                    mv.from == Square::from_alg(&disambig_string).unwrap()
-                })
-            }
-
-            _ => {
-                return Err(pgn::Error::new(
-                    pgn::ErrorKind::ParseError,
-                    format!("Invalid move disambiguation in {}", input),
-                ))
-            }
-        };
-
         let mut moves = vec![];
         self.generate_moves(&mut moves);
-        let filtered_moves = moves
-            .iter()
-            .filter(|mv| {
-                mv.to == dest_square
-                    && self.piece_at(mv.from).piece_type() == piece_type
-                    && mv.prom == promotion
-            })
-            .filter(|mv| move_filter(mv))
-            .collect::<Vec<_>>();
-
-        if filtered_moves.len() > 1 {
-            Err(pgn::Error::new(
-                pgn::ErrorKind::AmbiguousMove,
-                format!("{} could be any of {:?}", input, filtered_moves),
-            ))
-        } else if filtered_moves.is_empty() {
-            Err(pgn::Error::new(
-                pgn::ErrorKind::IllegalMove,
-                format!(
-                    "{} ({} to {}) {:?} {:?} is not a legal move in the position",
-                    input,
-                    piece_type,
-                    dest_square,
-                    moves
-                        .iter()
-                        .filter(|mv| move_filter(mv))
-                        .collect::<Vec<_>>(),
-                    moves
-                        .iter()
-                        .filter(|mv| mv.to == dest_square
-                            && self.piece_at(mv.from).piece_type() == piece_type)
-                        .collect::<Vec<_>>()
-                ),
-            ))
-        } else {
-            Ok(*filtered_moves[0])
-        }
+        self.move_from_san_in(input, &moves)
     }
 
     fn move_to_lan(&self, mv: &Self::Move) -> String {
+        if let Some(piece_type) = mv.drop_piece() {
+            return format!("{}@{}", piece_type.letter(), mv.to);
+        }
         let (file_from, rank_from) = mv.from.file_rank();
         let (file_to, rank_to) = mv.to.file_rank();
         let mut s: String = "".to_string();
@@ -557,6 +512,7 @@ impl PgnBoard for ChessBoard {
             Some(Rook) => s.push('r'),
             Some(Knight) => s.push('n'),
             Some(Bishop) => s.push('b'),
+            Some(King) => s.push('k'),
             None => (),
             _ => panic!("Illegal promotion move"),
         }
@@ -593,6 +549,9 @@ impl PgnBoard for ChessBoard {
                         Some('n') => Knight,
                         Some('B') => Bishop,
                         Some('b') => Bishop,
+                        // Only legal in variants such as Antichess
+                        Some('K') => King,
+                        Some('k') => King,
                         Some(ch) => {
                             return Err(pgn::Error::new(
                                 pgn::ErrorKind::ParseError,
@@ -636,12 +595,31 @@ impl Board for ChessBoard {
         let color = self.to_move;
         let piece_moved = self.piece_at(c_move.from).piece_type();
         let captured_piece: PieceType = self[c_move.to].piece_type();
+        let castling = self.castling_squares(c_move);
         let reverse_move = ChessReverseMove::from_move(c_move, self);
+        if let Some(history) = &mut self.history {
+            history.push(self.hash);
+        }
+
+        if let Some(piece_type) = c_move.drop_piece() {
+            // Dropping a piece from the hand, which the variant board has checked is legal
+            self.half_move_clock = self.half_move_clock.saturating_add(1);
+            self.move_num += 1;
+            self.set_piece(c_move.to, Piece::from_type_color(piece_type, color));
+            self.set_en_passant_square(None);
+            self.flip_side_to_move();
+            return reverse_move;
+        }
 
-        // Increment or reset the half-move clock
-        match (piece_moved, self.piece_at(c_move.to).piece_type()) {
+        // Increment or reset the half-move clock. Play may go on past the move counter draws,
+        // so the clock saturates rather than overflows
+        match (piece_moved, captured_piece) {
             (Pawn, _) => self.half_move_clock = 0,
-            (_, Empty) => self.half_move_clock += 1,
+            (_, Empty) => self.half_move_clock = self.half_move_clock.saturating_add(1),
+            // In Chess960, castling is encoded as the king capturing its own rook
+            (_, _) if castling.is_some() => {
+                self.half_move_clock = self.half_move_clock.saturating_add(1)
+            }
             (_, _) => self.half_move_clock = 0,
         }
 
@@ -649,66 +627,29 @@ impl Board for ChessBoard {
 
         // Perform castling
         // It will castle regardless of whether it is legal to do so
-        if piece_moved == King && (file_from as i8 - file_to as i8).abs() == 2 {
-            debug_assert_eq!(
-                file_from, 4,
-                "Tried to castle from the {}th file",
-                file_from
-            );
-
-            // Simple helper closure that moves a piece, emptying the square it came from
-            // Checks nothing, not even if the square has a piece. Use carefully.
-            let do_simple_move = |board: &mut Self, f_from, r_from, f_to, r_to| {
-                board.board[r_to as usize][f_to as usize] =
-                    board.board[r_from as usize][f_from as usize];
-                board.board[r_from as usize][f_from as usize] = Piece::empty();
-            };
-            // Assume castling is legal, and move the king and rook to where they should go
-            match (color, file_to) {
-                (White, 2) => {
-                    do_simple_move(self, 4, 7, 2, 7);
-                    do_simple_move(self, 0, 7, 3, 7);
-                }
-                (White, 6) => {
-                    do_simple_move(self, 4, 7, 6, 7);
-                    do_simple_move(self, 7, 7, 5, 7);
-                }
-                (Black, 2) => {
-                    do_simple_move(self, 4, 0, 2, 0);
-                    do_simple_move(self, 0, 0, 3, 0);
-                }
-                (Black, 6) => {
-                    do_simple_move(self, 4, 0, 6, 0);
-                    do_simple_move(self, 7, 0, 5, 0);
-                }
-                (_, _) => panic!(format!(
-                    "Error: Tried to castle to the {}th file. ",
-                    file_to
-                )),
-            }
+        if let Some(castling) = castling {
+            // In Chess960, the king and rook may land on each other's squares,
+            // so both are lifted before either is placed
+            self.set_piece(castling.king_from, Piece::empty());
+            self.set_piece(castling.rook_from, Piece::empty());
+            self.set_piece(castling.king_to, Piece::from_type_color(King, color));
+            self.set_piece(castling.rook_to, Piece::from_type_color(Rook, color));
         }
         // If a pawn takes towards an empty square, assume it is doing a legal en passant capture
         else if piece_moved == Pawn && file_from != file_to && captured_piece == Empty {
-            self.board[rank_to as usize][file_to as usize] =
-                self.board[rank_from as usize][file_from as usize];
-
-            self.board[rank_from as usize][file_to as usize] = Piece::empty();
-            self.board[rank_from as usize][file_from as usize] = Piece::empty();
+            self.move_piece(c_move.from, c_move.to);
+            self.set_piece(Square::from_ints(file_to, rank_from), Piece::empty());
         }
         // If it is not a special move
         else {
             // Does the move, depending on whether the move promotes or not
             match c_move.prom {
                 Some(piece_type) => {
-                    self.board[rank_to as usize][file_to as usize] =
-                        Piece::from_type_color(piece_type, self.side_to_move())
-                }
-                None => {
-                    self.board[rank_to as usize][file_to as usize] =
-                        self.board[rank_from as usize][file_from as usize]
+                    self.set_piece(c_move.from, Piece::empty());
+                    self.set_piece(c_move.to, Piece::from_type_color(piece_type, color));
                 }
+                None => self.move_piece(c_move.from, c_move.to),
             }
-            self.board[rank_from as usize][file_from as usize] = Piece::empty();
         }
 
         // Remove any en passant square. If it was available to this player,
@@ -736,104 +677,68 @@ impl Board for ChessBoard {
             if piece_moved == King {
                 self.disable_castling(color);
             }
-            // If a rook was moved, check if it came from a corner
-            if piece_moved == Rook {
-                match c_move.from {
-                    Square(0) => self.disable_castling_queenside(Black),
-                    Square(7) => self.disable_castling_kingside(Black),
-                    Square(56) => self.disable_castling_queenside(White),
-                    Square(63) => self.disable_castling_kingside(White),
-                    _ => (),
+            // If a piece moves from or to a castling rook's square,
+            // the rook has either moved or been captured
+            for &rook_color in &[White, Black] {
+                if c_move.from == self.castling_rook_square(rook_color, true)
+                    || c_move.to == self.castling_rook_square(rook_color, true)
+                {
+                    self.disable_castling_kingside(rook_color);
+                }
+                if c_move.from == self.castling_rook_square(rook_color, false)
+                    || c_move.to == self.castling_rook_square(rook_color, false)
+                {
+                    self.disable_castling_queenside(rook_color);
                 }
-            }
-            // For any piece, check if it goes into the corner
-            match c_move.to {
-                Square(0) => self.disable_castling_queenside(Black),
-                Square(7) => self.disable_castling_kingside(Black),
-                Square(56) => self.disable_castling_queenside(White),
-                Square(63) => self.disable_castling_kingside(White),
-                _ => (),
             }
         }
 
-        self.to_move = !self.to_move;
+        self.flip_side_to_move();
         reverse_move
     }
     fn reverse_move(&mut self, c_move: Self::ReverseMove) {
         let (file_from, rank_from) = c_move.from.file_rank();
-        let (file_to, rank_to) = c_move.to.file_rank();
+        let file_to = c_move.to.file();
         let piece_moved = self.piece_at(c_move.to).piece_type();
         let color = !self.to_move;
 
-        if piece_moved == King && (file_from as i8 - file_to as i8).abs() == 2 {
-            // Simple helper closure that moves a piece, emptying the square it came from
-            // Checks nothing, not even if the square has a piece. Use carefully.
-            let mut do_simple_move = |f_from, r_from, f_to, r_to| {
-                self.board[r_to as usize][f_to as usize] =
-                    self.board[r_from as usize][f_from as usize];
-                self.board[r_from as usize][f_from as usize] = Piece::empty();
-            };
-            // Assume castling is legal, and move the king and rook to where they should go
-            match (color, file_to) {
-                (White, 2) => {
-                    do_simple_move(2, 7, 4, 7);
-                    do_simple_move(3, 7, 0, 7);
-                }
-                (White, 6) => {
-                    do_simple_move(6, 7, 4, 7);
-                    do_simple_move(5, 7, 7, 7);
-                }
-                (Black, 2) => {
-                    do_simple_move(2, 0, 4, 0);
-                    do_simple_move(3, 0, 0, 0);
-                }
-                (Black, 6) => {
-                    do_simple_move(6, 0, 4, 0);
-                    do_simple_move(5, 0, 7, 0);
-                }
-                (_, _) => panic!(format!(
-                    "Error: Tried to castle to the {}th file. ",
-                    file_to
-                )),
-            }
+        if c_move.from == c_move.to {
+            // Take back a dropped piece
+            self.set_piece(c_move.to, Piece::empty());
+        } else if c_move.castling {
+            // Assume castling is legal, and move the king and rook back to where they came from
+            let castling = self.castling_for_move(color, c_move.from, c_move.to);
+            self.set_piece(castling.king_to, Piece::empty());
+            self.set_piece(castling.rook_to, Piece::empty());
+            self.set_piece(castling.king_from, Piece::from_type_color(King, color));
+            self.set_piece(castling.rook_from, Piece::from_type_color(Rook, color));
         }
         // Undo en passant capture
         else if piece_moved == Pawn && file_from != file_to && c_move.capture == Empty {
-            self.board[rank_from as usize][file_from as usize] =
-                self.board[rank_to as usize][file_to as usize];
-            self.board[rank_to as usize][file_to as usize] = Piece::empty();
-
-            match color {
-                Black => {
-                    self.board[rank_to as usize - 1][file_to as usize] =
-                        Piece::from_type_color(Pawn, White)
-                }
-                White => {
-                    self.board[rank_to as usize + 1][file_to as usize] =
-                        Piece::from_type_color(Pawn, Black)
-                }
-            }
+            self.move_piece(c_move.to, c_move.from);
+            self.set_piece(
+                Square::from_ints(file_to, rank_from),
+                Piece::from_type_color(Pawn, !color),
+            );
         } else {
             if c_move.prom {
-                self.board[rank_from as usize][file_from as usize] =
-                    Piece::from_type_color(Pawn, color);
-            } else {
-                self.board[rank_from as usize][file_from as usize] =
-                    self.board[rank_to as usize][file_to as usize];
-            }
-
-            if c_move.capture != Empty {
-                self.board[rank_to as usize][file_to as usize] =
-                    Piece::from_type_color(c_move.capture, self.to_move);
+                self.set_piece(c_move.from, Piece::from_type_color(Pawn, color));
             } else {
-                self.board[rank_to as usize][file_to as usize] = Piece::empty();
+                self.set_piece(c_move.from, self[c_move.to]);
             }
+            self.set_piece(
+                c_move.to,
+                Piece::from_type_color(c_move.capture, self.to_move),
+            );
         }
-        self.castling_en_passant = c_move.old_castling_en_passant;
+        self.set_castling_en_passant(c_move.old_castling_en_passant);
         self.half_move_clock = c_move.old_half_move_clock;
         self.move_num -= 1;
 
-        self.to_move = !self.to_move;
+        self.flip_side_to_move();
+        if let Some(history) = &mut self.history {
+            history.pop();
+        }
     }
 
     fn start_board() -> Self {
@@ -842,51 +747,13 @@ impl Board for ChessBoard {
 
     fn generate_moves(&self, moves: &mut Vec<Self::Move>) {
         debug_assert!(moves.is_empty());
-        let (mut active_moves, mut quiet_moves) = move_gen::all_legal_moves(self);
-        active_moves.append(&mut quiet_moves);
-        moves.extend_from_slice(&active_moves[..]);
+        let mut move_list = MoveList::new();
+        self.generate_legal_into(&mut move_list);
+        moves.extend_from_slice(&move_list);
     }
 
     fn game_result(&self) -> Option<board::GameResult> {
-        if self.half_move_clock >= 100 {
-            return Some(board::GameResult::Draw);
-        }
-        // TODO: This shouldn't call generate_moves(), but instead store whether its mate or not
-        let mut moves = vec![];
-        self.generate_moves(&mut moves);
-        if moves.is_empty() {
-            if move_gen::is_attacked(self, self.king_pos(self.side_to_move())) {
-                if self.to_move == White {
-                    Some(board::GameResult::BlackWin)
-                } else {
-                    Some(board::GameResult::WhiteWin)
-                }
-            } else {
-                Some(board::GameResult::Draw)
-            }
-        } else {
-            // Force a draw for unwinnable bishop/knight endgames
-            let mut black_material = 0.0;
-            let mut white_material = 0.0;
-            for square in BoardIter::new() {
-                // Games with queens, rooks or pawns are always undecided
-                match self[square].piece_type() {
-                    Queen | Rook | Pawn => return None,
-                    Knight | Bishop | King | Empty => (),
-                };
-                match (self[square].piece_type(), self[square].color()) {
-                    (piece_type, Some(Black)) => black_material += piece_type.value(),
-                    (piece_type, Some(White)) => white_material += piece_type.value(),
-                    (Empty, None) => (),
-                    (_, _) => unreachable!(),
-                };
-            }
-            if black_material <= 3.5 && white_material <= 3.5 {
-                Some(board::GameResult::Draw)
-            } else {
-                None
-            }
-        }
+        self.termination().map(Termination::result)
     }
 }
 
@@ -904,12 +771,11 @@ impl EvalBoard for ChessBoard {
             [0, 0, 0, 0, 0, 0, 0, 0],
         ];
         let mut value = 0.0;
-        for rank in 0..8 {
-            for file in 0..8 {
-                let piece = self.board[rank][file];
+        for (rank, pieces) in self.board.iter().enumerate() {
+            for (file, &piece) in pieces.iter().enumerate() {
                 let piece_val = piece.value();
                 let pos_val = POS_VALS[rank][file] as f32
-                    * if piece.color() == None {
+                    * if piece.color().is_none() {
                         0.0
                     } else {
                         match (piece.piece_type(), piece.color().unwrap()) {
@@ -926,7 +792,7 @@ impl EvalBoard for ChessBoard {
                             _ => 0.0,
                         }
                     };
-                let pawn_val = match self.board[rank][file].piece_type() {
+                let pawn_val = match piece.piece_type() {
                     Pawn => (rank as f32 - 3.5) * -0.1,
                     _ => 0.0,
                 };
@@ -939,10 +805,10 @@ impl EvalBoard for ChessBoard {
 
 impl ExtendedBoard for ChessBoard {
     type ReverseNullMove = ChessReverseNullMove;
-    type HashBoard = Self;
+    type HashBoard = u64;
 
-    fn hash_board(&self) -> Self {
-        self.clone()
+    fn hash_board(&self) -> u64 {
+        self.zobrist()
     }
 
     fn move_is_legal(&self, mv: Self::Move) -> bool {
@@ -950,19 +816,20 @@ impl ExtendedBoard for ChessBoard {
             return false;
         }
 
-        let mut moves1 = vec![];
-        let mut moves2 = vec![];
-        let mut moves3 = vec![];
-        let king_pos = move_gen::king_pos(&self);
-        let is_in_check = move_gen::is_attacked(&self, king_pos);
+        let mut moves1 = MoveList::new();
+        let mut moves2 = MoveList::new();
+        let mut moves3 = MoveList::new();
+        let check_info = move_gen::CheckInfo::new(self);
+        if check_info.checkers.has_several() && self[mv.from].piece_type() != King {
+            return false;
+        }
         move_gen::legal_moves_for_piece(
-            &mut self.clone(),
+            self,
             mv.from,
             &mut moves1,
             &mut moves2,
             &mut moves3,
-            is_in_check,
-            king_pos,
+            &check_info,
         );
         if moves1.contains(&mv) || moves2.contains(&mv) || moves3.contains(&mv) {
             let mut moves = vec![];
@@ -974,8 +841,9 @@ impl ExtendedBoard for ChessBoard {
     }
 
     fn active_moves(&self, moves: &mut Vec<Self::Move>) {
-        let (mut active_moves, _) = move_gen::all_legal_moves(self);
-        moves.append(&mut active_moves);
+        let mut active_moves = MoveList::new();
+        move_gen::generate_active_moves(self, &mut active_moves);
+        moves.extend_from_slice(&active_moves);
     }
 
     fn null_move_is_available(&self) -> bool {
@@ -987,16 +855,26 @@ impl ExtendedBoard for ChessBoard {
 
         let reverse_move = ChessReverseNullMove {
             old_castling_en_passant: self.castling_en_passant,
+            old_half_move_clock: self.half_move_clock,
         };
+        if let Some(history) = &mut self.history {
+            history.push(self.hash);
+        }
+        // Positions before a null move cannot be repeated after it
+        self.half_move_clock = 0;
         self.set_en_passant_square(None);
-        self.to_move = !self.to_move;
+        self.flip_side_to_move();
 
         reverse_move
     }
 
     fn reverse_null_move(&mut self, mv: <Self as ExtendedBoard>::ReverseNullMove) {
-        self.castling_en_passant = mv.old_castling_en_passant;
-        self.to_move = !self.to_move;
+        self.set_castling_en_passant(mv.old_castling_en_passant);
+        self.half_move_clock = mv.old_half_move_clock;
+        self.flip_side_to_move();
+        if let Some(history) = &mut self.history {
+            history.pop();
+        }
     }
 }
 
@@ -1029,33 +907,27 @@ impl ops::Index<Square> for ChessBoard {
     type Output = Piece;
     fn index(&self, square: Square) -> &Piece {
         let Square(i) = square;
-        debug_assert!(
-            i < 64,
-            format!("Tried to find piece at pos {} on board{}!", i, self)
-        );
+        debug_assert!(i < 64, "Tried to find piece at pos {} on board{}!", i, self);
         &self.board[i as usize >> 3][i as usize & 0b0000_0111]
     }
 }
 
-impl ops::IndexMut<Square> for ChessBoard {
-    fn index_mut(&mut self, square: Square) -> &mut Piece {
-        let Square(i) = square;
-        debug_assert!(
-            i < 64,
-            format!("Tried to find piece at pos {} on board{}!", i, self)
-        );
-        &mut self.board[i as usize >> 3][i as usize & 0b0000_0111]
-    }
-}
-
 impl ChessBoard {
     pub fn empty() -> Self {
+        let mut piece_type_bitboards = [BitBoard::empty(); 7];
+        piece_type_bitboards[Empty as usize] = BitBoard::full();
         Self {
             board: [[Piece::Empty; 8]; 8],
+            piece_type_bitboards,
+            color_bitboards: [BitBoard::empty(); 2],
             to_move: White,
             castling_en_passant: 0,
             half_move_clock: 0,
             move_num: 0,
+            hash: 0,
+            history: None,
+            chess960: false,
+            castling_rook_files: STANDARD_CASTLING_ROOK_FILES,
         }
     }
 
@@ -1064,40 +936,87 @@ impl ChessBoard {
         self[square]
     }
 
+    /// Places a piece on the square, replacing whatever was there.
+    /// Placing `Piece::Empty` clears the square.
+    pub fn set_piece(&mut self, square: Square, piece: Piece) {
+        let old_piece = self[square];
+        let bit = BitBoard::from_square(square);
+
+        self.piece_type_bitboards[old_piece.piece_type() as usize] ^= bit;
+        if let Some(color) = old_piece.color() {
+            self.color_bitboards[color.disc()] ^= bit;
+        }
+
+        self.piece_type_bitboards[piece.piece_type() as usize] ^= bit;
+        if let Some(color) = piece.color() {
+            self.color_bitboards[color.disc()] ^= bit;
+        }
+
+        self.hash ^= zobrist::piece_key(old_piece, square) ^ zobrist::piece_key(piece, square);
+
+        self.board[square.rank() as usize][square.file() as usize] = piece;
+    }
+
+    /// Moves the piece on `from` to `to`, capturing anything on `to`
+    fn move_piece(&mut self, from: Square, to: Square) {
+        let piece = self[from];
+        self.set_piece(from, Piece::empty());
+        self.set_piece(to, piece);
+    }
+
+    /// Returns every square with a piece of the given type, of either color.
+    /// For `Empty`, returns every empty square.
+    pub fn piece_type_bitboard(&self, piece_type: PieceType) -> BitBoard {
+        self.piece_type_bitboards[piece_type as usize]
+    }
+
+    pub fn color_bitboard(&self, color: Color) -> BitBoard {
+        self.color_bitboards[color.disc()]
+    }
+
+    pub fn piece_bitboard(&self, piece_type: PieceType, color: Color) -> BitBoard {
+        self.piece_type_bitboard(piece_type) & self.color_bitboard(color)
+    }
+
+    pub fn occupied(&self) -> BitBoard {
+        self.color_bitboards[0] | self.color_bitboards[1]
+    }
+
+    /// Returns the square of `color`'s king.
+    /// Panics if there is none, which variants like Horde must check with `pos_of` first.
     pub fn king_pos(&self, color: Color) -> Square {
-        match self.pos_of(Piece::from_type_color(King, color)) {
+        match self.piece_bitboard(King, color).first_square() {
             Some(square) => square,
             None => panic!("Error: There is no king on the board:\n{}", self),
         }
     }
 
     pub fn pos_of(&self, piece: Piece) -> Option<Square> {
-        for i in 0..64 {
-            if self.piece_at(Square(i)) == piece {
-                return Some(Square(i));
-            }
+        match piece.color() {
+            Some(color) => self.piece_bitboard(piece.piece_type(), color),
+            None => self.piece_type_bitboard(Empty),
         }
-        None
+        .first_square()
     }
 
     pub fn disable_castling(&mut self, color: Color) {
         match color {
-            White => self.castling_en_passant &= 0b1111_1100,
-            Black => self.castling_en_passant &= 0b1111_0011,
+            White => self.set_castling_en_passant(self.castling_en_passant & 0b1111_1100),
+            Black => self.set_castling_en_passant(self.castling_en_passant & 0b1111_0011),
         }
     }
 
     pub fn disable_castling_queenside(&mut self, color: Color) {
         match color {
-            White => self.castling_en_passant &= 0b1111_1101,
-            Black => self.castling_en_passant &= 0b1111_0111,
+            White => self.set_castling_en_passant(self.castling_en_passant & 0b1111_1101),
+            Black => self.set_castling_en_passant(self.castling_en_passant & 0b1111_0111),
         }
     }
 
     pub fn disable_castling_kingside(&mut self, color: Color) {
         match color {
-            White => self.castling_en_passant &= 0b1111_1110,
-            Black => self.castling_en_passant &= 0b1111_1011,
+            White => self.set_castling_en_passant(self.castling_en_passant & 0b1111_1110),
+            Black => self.set_castling_en_passant(self.castling_en_passant & 0b1111_1011),
         }
     }
 
@@ -1114,6 +1033,436 @@ impl ChessBoard {
         }
     }
 
+    /// Returns whether castling moves are encoded as the king capturing its own rook, as in UCI_Chess960.
+    /// Otherwise, castling is encoded as the king moving two squares.
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Sets how castling moves are encoded. This is set automatically when reading a FEN string
+    /// where the king or the castling rooks are not on their standard files,
+    /// and must not be unset for such positions.
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    /// Returns the file of the rook that castles to the given side.
+    /// In Double Fischer Random, this may differ between the colors.
+    pub fn castling_rook_file(&self, color: Color, kingside: bool) -> u8 {
+        self.castling_rook_files[color.disc()][if kingside { 0 } else { 1 }]
+    }
+
+    /// Returns the starting square of the rook that castles to the given side
+    pub fn castling_rook_square(&self, color: Color, kingside: bool) -> Square {
+        let rank = if color == White { 7 } else { 0 };
+        Square::from_ints(self.castling_rook_file(color, kingside), rank)
+    }
+
+    /// Returns the move that castles to the given side, without checking that it is legal
+    pub fn castling_move(&self, color: Color, kingside: bool) -> ChessMove {
+        let king_square = self.king_pos(color);
+        if self.chess960 {
+            ChessMove::new(king_square, self.castling_rook_square(color, kingside))
+        } else {
+            let king_file = if kingside { 6 } else { 2 };
+            ChessMove::new(
+                king_square,
+                Square::from_ints(king_file, king_square.rank()),
+            )
+        }
+    }
+
+    /// Returns whether the move is castling, assuming it is legal
+    pub fn is_castling(&self, mv: ChessMove) -> bool {
+        self.castling_squares(mv).is_some()
+    }
+
+    /// Returns where the king and rook move, if the move is castling.
+    /// Assumes the move is legal.
+    pub fn castling_squares(&self, mv: ChessMove) -> Option<Castling> {
+        let piece = self[mv.from];
+        let color = match piece.color() {
+            Some(color) if piece.piece_type() == King => color,
+            _ => return None,
+        };
+        let is_castling = if self.chess960 {
+            self[mv.to] == Piece::from_type_color(Rook, color)
+        } else {
+            (mv.from.file() as i8 - mv.to.file() as i8).abs() == 2
+        };
+        if is_castling {
+            Some(self.castling_for_move(color, mv.from, mv.to))
+        } else {
+            None
+        }
+    }
+
+    /// Returns where the king and rook move for a castling move, encoded as `from` and `to`
+    fn castling_for_move(&self, color: Color, from: Square, to: Square) -> Castling {
+        let kingside = to.file() > from.file();
+        let rank = from.rank();
+        let (king_file, rook_file) = if kingside { (6, 5) } else { (2, 3) };
+        Castling {
+            king_from: from,
+            king_to: Square::from_ints(king_file, rank),
+            rook_from: Square::from_ints(self.castling_rook_file(color, kingside), rank),
+            rook_to: Square::from_ints(rook_file, rank),
+        }
+    }
+
+    /// Writes a move in SAN, without the check or mate suffix.
+    /// Ambiguities are resolved against `legal_moves`, so that variants with different legal moves can use it
+    pub(crate) fn move_to_san_in(&self, mv: &ChessMove, legal_moves: &[ChessMove]) -> String {
+        let piece_type = self.piece_at(mv.from).piece_type();
+
+        let mut output = String::new();
+
+        if let Some(castling) = self.castling_squares(*mv) {
+            if castling.is_kingside() {
+                output.push_str("0-0");
+            } else {
+                output.push_str("0-0-0");
+            }
+        } else {
+            if piece_type != Pawn {
+                output.push(piece_type.letter());
+            }
+
+            let alternative_from_squares = legal_moves
+                .iter()
+                .filter(|&cand_mv| cand_mv.from != mv.from)
+                .filter(|&cand_mv| {
+                    cand_mv.to == mv.to && self.piece_at(cand_mv.from) == self.piece_at(mv.from)
+                })
+                .map(|cand_mv| cand_mv.from)
+                .collect::<Vec<_>>();
+
+            if alternative_from_squares.is_empty()
+                && (piece_type != Pawn || mv.from.file() == mv.to.file())
+            {
+            }
+            // Disambiguate with departure file
+            else if alternative_from_squares
+                .iter()
+                .all(|square| square.file() != mv.from.file())
+            {
+                output.push(mv.from.to_string().chars().next().unwrap());
+            }
+            // Disambiguate with departure rank
+            else if alternative_from_squares
+                .iter()
+                .all(|square| square.rank() != mv.from.rank())
+            {
+                output.push(mv.from.to_string().chars().last().unwrap());
+            }
+            // Disambiguate with full square
+            else {
+                output.push_str(&mv.from.to_string());
+            };
+
+            if self.piece_at(mv.to) != Piece::Empty {
+                output.push('x');
+            }
+
+            output.push_str(&mv.to.to_string());
+
+            match mv.prom {
+                Some(Queen) => output.push_str("=Q"),
+                Some(Rook) => output.push_str("=R"),
+                Some(Knight) => output.push_str("=N"),
+                Some(Bishop) => output.push_str("=B"),
+                // Only in variants like Antichess
+                Some(King) => output.push_str("=K"),
+                None => (),
+                _ => panic!("Illegal promotion move"),
+            };
+        }
+
+        output
+    }
+
+    /// Parses a move in SAN, and finds it among `legal_moves`
+    pub(crate) fn move_from_san_in(
+        &self,
+        input: &str,
+        legal_moves: &[ChessMove],
+    ) -> Result<ChessMove, pgn::Error> {
+        // Castling may be written with zeros or with the letter O, which PGN uses
+        let kingside = match input.trim_end_matches(&['+', '#'][..]) {
+            "0-0-0" | "O-O-O" => Some(false),
+            "0-0" | "O-O" => Some(true),
+            _ => None,
+        };
+        if let Some(kingside) = kingside {
+            let color = self.side_to_move();
+            let has_right = if kingside {
+                self.can_castle_kingside(color)
+            } else {
+                self.can_castle_queenside(color)
+            };
+            // Variants may have no king to castle with
+            if has_right && !self.piece_bitboard(King, color).is_empty() {
+                let mv = self.castling_move(color, kingside);
+                if legal_moves.contains(&mv) {
+                    return Ok(mv);
+                }
+            }
+            return Err(pgn::Error::new(
+                pgn::ErrorKind::IllegalMove,
+                format!("{} is not legal in the position", input),
+            ));
+        }
+
+        if input.chars().count() < 2 {
+            return Err(pgn::Error::new(
+                pgn::ErrorKind::ParseError,
+                format!(
+                    "Invalid move {}: Too short at length {:?}",
+                    input,
+                    input.chars().count()
+                ),
+            ));
+        }
+
+        let piece_type =
+            PieceType::from_letter(input.chars().next().unwrap()).unwrap_or(PieceType::Pawn);
+
+        let mut reverse_chars = input.chars().rev().peekable();
+
+        if reverse_chars.peek() == Some(&'+') || reverse_chars.peek() == Some(&'#') {
+            reverse_chars.next();
+        }
+
+        let promotion = reverse_chars
+            .peek()
+            .cloned()
+            .and_then(PieceType::from_letter);
+        if promotion.is_some() {
+            reverse_chars.next();
+            if reverse_chars.next() != Some('=') {
+                return Err(pgn::Error::new(
+                    pgn::ErrorKind::ParseError,
+                    format!("Illegal promotion on {}", input),
+                ));
+            }
+        }
+
+        let dest_square = Square::from_alg(
+            &[reverse_chars.next(), reverse_chars.next()]
+                .iter()
+                .rev()
+                .filter_map(|&ch| ch)
+                .collect::<String>(),
+        )
+        .map_err(|err| {
+            pgn::Error::new_caused_by(
+                pgn::ErrorKind::ParseError,
+                format!("Illegal move {}", input),
+                err,
+            )
+        })?;
+
+        if reverse_chars.peek() == Some(&'x') {
+            reverse_chars.next();
+        }
+
+        let mut disambig_string = reverse_chars
+            .take_while(|&ch| PieceType::from_letter(ch).is_none())
+            .collect::<String>();
+        disambig_string = disambig_string.chars().rev().collect();
+
+        let move_filter: Box<dyn Fn(&ChessMove) -> bool> = match (
+            disambig_string.chars().count(),
+            disambig_string.chars().next(),
+        ) {
+            (0, None) => Box::new(|_| true),
+
+            (1, Some(rank)) if ('1'..='8').contains(&rank) => {
+                Box::new(move |mv: &ChessMove| mv.from.rank() == 7 - (rank as u8 - b'1'))
+            }
+
+            (1, Some(file)) if ('a'..='h').contains(&file) => {
+                Box::new(move |mv: &ChessMove| mv.from.file() == (file as u8 - b'a'))
+            }
+
+            (2, _) if Square::from_alg(&disambig_string).is_ok() => {
+                Box::new(move |mv: &ChessMove| {
+                    mv.from == Square::from_alg(&disambig_string).unwrap()
+                })
+            }
+
+            _ => {
+                return Err(pgn::Error::new(
+                    pgn::ErrorKind::ParseError,
+                    format!("Invalid move disambiguation in {}", input),
+                ))
+            }
+        };
+
+        let filtered_moves = legal_moves
+            .iter()
+            .filter(|mv| {
+                mv.to == dest_square
+                    && self.piece_at(mv.from).piece_type() == piece_type
+                    && mv.prom == promotion
+            })
+            .filter(|mv| move_filter(mv))
+            .collect::<Vec<_>>();
+
+        if filtered_moves.len() > 1 {
+            Err(pgn::Error::new(
+                pgn::ErrorKind::AmbiguousMove,
+                format!("{} could be any of {:?}", input, filtered_moves),
+            ))
+        } else if filtered_moves.is_empty() {
+            Err(pgn::Error::new(
+                pgn::ErrorKind::IllegalMove,
+                format!(
+                    "{} ({} to {}) {:?} {:?} is not a legal move in the position",
+                    input,
+                    piece_type,
+                    dest_square,
+                    legal_moves
+                        .iter()
+                        .filter(|mv| move_filter(mv))
+                        .collect::<Vec<_>>(),
+                    legal_moves
+                        .iter()
+                        .filter(|mv| mv.to == dest_square
+                            && self.piece_at(mv.from).piece_type() == piece_type)
+                        .collect::<Vec<_>>()
+                ),
+            ))
+        } else {
+            Ok(*filtered_moves[0])
+        }
+    }
+
+    /// Returns the castling field of the FEN string.
+    /// Shredder-FEN names every castling rook by its file. Otherwise, X-FEN is used,
+    /// which is the same as standard FEN except for rooks that are not the outermost on their side.
+    fn fen_castling_field(&self, shredder: bool) -> String {
+        let mut field = String::new();
+        for &color in &[White, Black] {
+            for &kingside in &[true, false] {
+                let can_castle = if kingside {
+                    self.can_castle_kingside(color)
+                } else {
+                    self.can_castle_queenside(color)
+                };
+                if !can_castle {
+                    continue;
+                }
+                let rook_file = self.castling_rook_file(color, kingside);
+                let king_file = self.king_pos(color).file();
+                let back_rank = BitBoard::rank(if color == White { 7 } else { 0 });
+                let is_outermost = (self.piece_bitboard(Rook, color) & back_rank)
+                    .squares()
+                    .map(Square::file)
+                    .all(|file| {
+                        if kingside {
+                            file <= rook_file || file < king_file
+                        } else {
+                            file >= rook_file || file > king_file
+                        }
+                    });
+                let letter = if !shredder && is_outermost {
+                    if kingside {
+                        'K'
+                    } else {
+                        'Q'
+                    }
+                } else {
+                    (b'A' + rook_file) as char
+                };
+                match color {
+                    White => field.push(letter),
+                    Black => field.push(letter.to_ascii_lowercase()),
+                }
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    /// Returns the FEN string of the position, with the castling field in Shredder-FEN,
+    /// where every castling rook is named by its file.
+    pub fn to_shredder_fen(&self) -> String {
+        let fen = self.to_fen();
+        let mut fields: Vec<&str> = fen.split(' ').collect();
+        let castling_field = self.fen_castling_field(true);
+        fields[2] = &castling_field;
+        fields.join(" ")
+    }
+
+    /// Returns the Chess960 start position with the given index, from 0 to 959.
+    /// Uses the standard numbering scheme, where 518 is the standard start position.
+    /// Castling moves are encoded as the king capturing its own rook, even for the standard position.
+    pub fn chess960_start_board(index: u16) -> Option<Self> {
+        Self::double_chess960_start_board(index, index)
+    }
+
+    /// Returns the Double Fischer Random start position where White's and Black's back ranks
+    /// are the Chess960 start positions with the given indices, each from 0 to 959.
+    pub fn double_chess960_start_board(white_index: u16, black_index: u16) -> Option<Self> {
+        if white_index >= 960 || black_index >= 960 {
+            return None;
+        }
+        let back_rank = |index| {
+            chess960_back_rank(index)
+                .iter()
+                .map(|piece_type| piece_type.letter())
+                .collect::<String>()
+        };
+        let fen = format!(
+            "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+            back_rank(black_index).to_ascii_lowercase(),
+            back_rank(white_index)
+        );
+        let mut board = Self::from_fen(&fen).unwrap();
+        board.chess960 = true;
+        Some(board)
+    }
+
+    /// Generates all legal moves into a fixed-capacity list, without allocating.
+    /// `generate_moves` is a thin wrapper around this.
+    pub fn generate_legal_into(&self, moves: &mut MoveList) {
+        move_gen::generate_legal_into(self, moves)
+    }
+
+    /// Returns whether a legal move gives check, without playing it
+    pub fn gives_check(&self, mv: ChessMove) -> bool {
+        move_gen::gives_check(self, mv)
+    }
+
+    /// Returns the squares of every piece of `color` that attacks the square
+    pub fn attackers_to(&self, square: Square, color: Color) -> BitBoard {
+        move_gen::attackers_to(self, square, color)
+    }
+
+    /// Returns the squares of every piece giving check to the side to move
+    pub fn checkers(&self) -> BitBoard {
+        move_gen::checkers(self)
+    }
+
+    /// Returns every square attacked by a piece of `color`
+    pub fn attacked_squares(&self, color: Color) -> BitBoard {
+        move_gen::attacked_squares(self, color)
+    }
+
+    /// Returns the static exchange evaluation of a legal move, in centipawns.
+    /// See `move_gen::see` for details.
+    pub fn see(&self, mv: ChessMove) -> i32 {
+        move_gen::see(self, mv)
+    }
+
+    /// Returns whether the static exchange evaluation of a legal move is at least `threshold`
+    pub fn see_ge(&self, mv: ChessMove, threshold: i32) -> bool {
+        move_gen::see_ge(self, mv, threshold)
+    }
+
     pub fn en_passant_square(&self) -> Option<Square> {
         if self.castling_en_passant & 0b1111_0000 != 0 {
             let rank = if self.to_move == Black { 5 } else { 2 };
@@ -1127,13 +1476,200 @@ impl ChessBoard {
     }
 
     pub fn set_en_passant_square(&mut self, square: Option<Square>) {
+        let castling_rights = self.castling_en_passant & 0b0000_1111;
         match square {
             Some(square) => {
-                let (mut byte, _) = square.file_rank();
-                byte |= 0b1000_0000;
-                self.castling_en_passant |= (byte << 4) | 0b1000_0000;
+                self.set_castling_en_passant(castling_rights | (square.file() << 4) | 0b1000_0000)
+            }
+            None => self.set_castling_en_passant(castling_rights),
+        }
+    }
+
+    /// Returns why the game has ended, if it has.
+    ///
+    /// Claimable draws are reported as well, see `Termination::is_claimable`.
+    /// Checkmate and stalemate take precedence over draws by the move counter or by repetition,
+    /// and automatic draws take precedence over claimable ones.
+    pub fn termination(&self) -> Option<Termination> {
+        self.termination_with_material_rule(true)
+    }
+
+    /// Returns why the game has ended. Without the insufficient material rule,
+    /// as in variants that can be won without checkmate, only kings and minor pieces play on.
+    pub(crate) fn termination_with_material_rule(
+        &self,
+        insufficient_material: bool,
+    ) -> Option<Termination> {
+        if !move_gen::has_legal_moves(self) {
+            return if move_gen::is_attacked(self, self.king_pos(self.side_to_move())) {
+                Some(Termination::Checkmate {
+                    winner: !self.side_to_move(),
+                })
+            } else {
+                Some(Termination::Stalemate)
+            };
+        }
+        if self.half_move_clock >= 150 {
+            Some(Termination::SeventyFiveMoveRule)
+        } else if self.is_repetition(5) {
+            Some(Termination::FivefoldRepetition)
+        } else if insufficient_material && self.is_insufficient_material() {
+            Some(Termination::InsufficientMaterial)
+        } else if self.is_repetition(3) {
+            Some(Termination::ThreefoldRepetition)
+        } else if self.half_move_clock >= 100 {
+            Some(Termination::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether neither side can checkmate by any sequence of legal moves,
+    /// because of the material left on the board.
+    ///
+    /// This is the case with only kings, kings and a single knight or bishop,
+    /// or kings and any number of bishops that all stand on squares of the same color.
+    pub fn is_insufficient_material(&self) -> bool {
+        !self.can_mate(White) && !self.can_mate(Black)
+    }
+
+    /// Returns whether `color` has the material to checkmate, with the help of any opponent pieces.
+    /// When a player runs out of time and the opponent cannot mate, the game is drawn.
+    pub fn can_mate(&self, color: Color) -> bool {
+        let heavy_pieces = self.piece_bitboard(Pawn, color)
+            | self.piece_bitboard(Rook, color)
+            | self.piece_bitboard(Queen, color);
+        if !heavy_pieces.is_empty() {
+            return true;
+        }
+        let knights = self.piece_bitboard(Knight, color);
+        let bishops = self.piece_bitboard(Bishop, color);
+        // The opponent's pieces may be needed to block its own king
+        let opponent_pieces = self.color_bitboard(!color) & !self.piece_type_bitboard(King);
+
+        match (knights.is_empty(), bishops.is_empty()) {
+            (true, true) => false,
+            (false, false) => true,
+            (false, true) => knights.has_several() || !opponent_pieces.is_empty(),
+            (true, false) => {
+                let light_bishops = bishops & BitBoard::light_squares();
+                let dark_bishops = bishops & BitBoard::dark_squares();
+                if !light_bishops.is_empty() && !dark_bishops.is_empty() {
+                    return true;
+                }
+                // Bishops on one color can never cover the squares of the other color,
+                // so the opponent needs a piece that can stand on them
+                let bishop_squares = if light_bishops.is_empty() {
+                    BitBoard::dark_squares()
+                } else {
+                    BitBoard::light_squares()
+                };
+                let blockers =
+                    opponent_pieces & !(self.piece_type_bitboard(Bishop) & bishop_squares);
+                !blockers.is_empty()
             }
-            None => self.castling_en_passant &= 0b0000_1111,
         }
     }
+
+    /// Returns whether the position is dead, meaning that no sequence of legal moves leads to checkmate.
+    ///
+    /// Besides insufficient material, this searches the positions reachable from this one,
+    /// such as king moves behind a locked pawn chain. The position is only considered dead if the search
+    /// finishes within `max_positions` positions, without finding a checkmate, a capture or a pawn move.
+    /// Returns false when the search gives up, so that a live position is never reported as dead.
+    pub fn is_dead_position(&self, max_positions: usize) -> bool {
+        if self.is_insufficient_material() {
+            return true;
+        }
+        let mut root = self.clone();
+        root.disable_history();
+        // The move counter does not matter for whether mate is possible
+        root.half_move_clock = 0;
+        let mut visited = HashSet::new();
+        visited.insert(root.zobrist());
+        let mut stack = vec![root];
+        let mut moves = vec![];
+
+        while let Some(board) = stack.pop() {
+            moves.clear();
+            board.generate_moves(&mut moves);
+            if moves.is_empty() && move_gen::is_attacked(&board, board.king_pos(board.to_move)) {
+                return false;
+            }
+            for &mv in moves.iter() {
+                if !board[mv.to].is_empty() || board[mv.from].piece_type() == Pawn {
+                    // The material or the pawn structure would change, so the search cannot tell
+                    return false;
+                }
+                let mut child = board.clone();
+                child.do_move(mv);
+                child.half_move_clock = 0;
+                if visited.insert(child.zobrist()) {
+                    if visited.len() > max_positions {
+                        return false;
+                    }
+                    stack.push(child);
+                }
+            }
+        }
+        true
+    }
+
+    /// Starts recording the keys of positions reached from now on, so that repetitions can be detected.
+    /// Has no effect if the history is already enabled.
+    pub fn enable_history(&mut self) {
+        if self.history.is_none() {
+            self.history = Some(vec![]);
+        }
+    }
+
+    /// Stops recording positions, and forgets the ones recorded so far
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Returns whether the current position has occurred at least `count` times, including now.
+    ///
+    /// Only positions since the last capture or pawn move are compared, as no earlier position can repeat.
+    /// A threefold repetition lets either player claim a draw, and a fivefold repetition is drawn automatically.
+    /// Always returns false for counts above 1 if the history is not enabled.
+    pub fn is_repetition(&self, count: usize) -> bool {
+        let history = match &self.history {
+            Some(history) => history,
+            None => return count <= 1,
+        };
+        let window = usize::min(self.half_move_clock as usize, history.len());
+        let repetitions = history[history.len() - window..]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&key| key == self.hash)
+            .count();
+        repetitions + 1 >= count
+    }
+
+    /// Returns the Zobrist key of the position.
+    /// It is updated incrementally when moves are made, and covers the pieces,
+    /// the side to move, the castling rights and the en passant square.
+    pub fn zobrist(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns the pieces on the board, indexed by rank and then by file, starting from a8
+    pub fn squares(&self) -> &[[Piece; 8]; 8] {
+        &self.board
+    }
+
+    /// Sets the packed castling rights and en passant square, keeping the hash in sync
+    fn set_castling_en_passant(&mut self, castling_en_passant: u8) {
+        self.hash ^= zobrist::castling_en_passant_key(self.castling_en_passant)
+            ^ zobrist::castling_en_passant_key(castling_en_passant);
+        self.castling_en_passant = castling_en_passant;
+    }
+
+    fn flip_side_to_move(&mut self) {
+        self.hash ^= zobrist::side_to_move_key(Black);
+        self.to_move = !self.to_move;
+    }
 }
@@ -0,0 +1,198 @@
+//! A game of chess: a board together with its starting position, the moves played and the game's tags.
+
+use crate::chess_board::ChessBoard;
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use crate::pgn_reader::{PgnGame, PgnMove};
+use board_game_traits::board::{Board, Color, GameResult};
+use std::fmt;
+
+/// A chess game that remembers its moves, so they can be undone and redone.
+///
+/// The board records its position history, so repetitions are detected.
+/// Undone moves can be redone until a different move is played.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChessGame {
+    start_board: ChessBoard,
+    board: ChessBoard,
+    // Every move played, including the undone moves that can be redone
+    moves: Vec<ChessMove>,
+    // One for each move currently played on the board
+    reverse_moves: Vec<ChessReverseMove>,
+    tags: Vec<(String, String)>,
+    // A result set by the players, such as a resignation or an agreed draw
+    result: Option<GameResult>,
+}
+
+impl ChessGame {
+    /// Creates a game without moves or tags, starting from the position
+    pub fn new(start_board: ChessBoard) -> Self {
+        let mut board = start_board.clone();
+        board.enable_history();
+        ChessGame {
+            start_board,
+            board,
+            moves: vec![],
+            reverse_moves: vec![],
+            tags: vec![],
+            result: None,
+        }
+    }
+
+    /// Creates a game from the main line, tags and result of a game read from PGN.
+    /// The board is at the end of the main line.
+    pub fn from_pgn_game(pgn_game: &PgnGame) -> Self {
+        let mut game = ChessGame::new(pgn_game.start_board.clone());
+        for pgn_move in pgn_game.mainline.moves.iter() {
+            game.play(pgn_move.mv);
+        }
+        game.tags = pgn_game.tags.clone();
+        // The termination marker repeats the board's own result, unless the game ended otherwise
+        if pgn_game.result != game.board.game_result() {
+            game.result = pgn_game.result;
+        }
+        game
+    }
+
+    /// Returns the tags and the moves played so far, as a game for the PGN writer
+    pub fn to_pgn_game(&self) -> PgnGame {
+        let mut pgn_game = PgnGame::new(self.start_board.clone());
+        pgn_game.tags = self.tags.clone();
+        pgn_game.mainline.moves = self.moves().iter().map(|&mv| PgnMove::new(mv)).collect();
+        pgn_game.result = self.game_result();
+        pgn_game
+    }
+
+    /// Returns the position the game started from
+    pub fn start_position(&self) -> &ChessBoard {
+        &self.start_board
+    }
+
+    /// Returns the current position
+    pub fn board(&self) -> &ChessBoard {
+        &self.board
+    }
+
+    /// Returns the moves played to reach the current position
+    pub fn moves(&self) -> &[ChessMove] {
+        &self.moves[..self.ply()]
+    }
+
+    /// Returns the number of moves played to reach the current position
+    pub fn ply(&self) -> usize {
+        self.reverse_moves.len()
+    }
+
+    /// Returns the number of moves in the game, including undone moves that can be redone
+    pub fn num_moves(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Plays a move, which is assumed to be legal. Any undone moves can no longer be redone.
+    pub fn play(&mut self, mv: ChessMove) -> ChessReverseMove {
+        self.moves.truncate(self.ply());
+        self.moves.push(mv);
+        let reverse_move = self.board.do_move(mv);
+        self.reverse_moves.push(reverse_move);
+        reverse_move
+    }
+
+    /// Takes back the last move played, and returns it. Returns `None` at the start of the game
+    pub fn undo(&mut self) -> Option<ChessMove> {
+        let reverse_move = self.reverse_moves.pop()?;
+        self.board.reverse_move(reverse_move);
+        Some(self.moves[self.ply()])
+    }
+
+    /// Plays the next undone move again, and returns it. Returns `None` if no moves have been undone
+    pub fn redo(&mut self) -> Option<ChessMove> {
+        let mv = *self.moves.get(self.ply())?;
+        self.reverse_moves.push(self.board.do_move(mv));
+        Some(mv)
+    }
+
+    /// Undoes or redoes moves until `ply` moves have been played.
+    /// Returns false, and leaves the game unchanged, if the game has fewer moves.
+    pub fn go_to_ply(&mut self, ply: usize) -> bool {
+        if ply > self.num_moves() {
+            return false;
+        }
+        while self.ply() > ply {
+            self.undo();
+        }
+        while self.ply() < ply {
+            self.redo();
+        }
+        true
+    }
+
+    /// Returns the tag pairs, in the order they were set
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    /// Returns the value of the tag with the name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, replacing any earlier value
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Ends the game with a result that does not follow from the position, like a resignation.
+    /// Setting `None` clears it. The result is kept when moves are undone or played.
+    pub fn set_result(&mut self, result: Option<GameResult>) {
+        self.result = result;
+    }
+}
+
+impl Default for ChessGame {
+    fn default() -> Self {
+        ChessGame::new(ChessBoard::start_board())
+    }
+}
+
+impl Board for ChessGame {
+    type Move = ChessMove;
+    type ReverseMove = ChessReverseMove;
+
+    fn start_board() -> Self {
+        ChessGame::new(ChessBoard::start_board())
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.board.side_to_move()
+    }
+
+    fn generate_moves(&self, moves: &mut Vec<Self::Move>) {
+        self.board.generate_moves(moves)
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        self.play(mv)
+    }
+
+    /// Takes back the last move. The move can be redone afterwards
+    fn reverse_move(&mut self, reverse_move: Self::ReverseMove) {
+        debug_assert_eq!(self.reverse_moves.last(), Some(&reverse_move));
+        self.undo();
+    }
+
+    /// Returns the result set with `set_result`, or else the result of the position
+    fn game_result(&self) -> Option<GameResult> {
+        self.result.or_else(|| self.board.game_result())
+    }
+}
+
+impl fmt::Display for ChessGame {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(&self.board, fmt)
+    }
+}
@@ -10,6 +10,7 @@ pub struct ChessReverseMove {
     pub to: Square,
     pub capture: PieceType,
     pub prom: bool,
+    pub castling: bool,
     pub old_castling_en_passant: u8,
     pub old_half_move_clock: u8,
 }
@@ -23,6 +24,7 @@ impl ChessReverseMove {
             to: c_move.to,
             capture: board[c_move.to].piece_type(),
             prom: c_move.prom.is_some(),
+            castling: board.is_castling(c_move),
             old_castling_en_passant: board.castling_en_passant,
             old_half_move_clock: board.half_move_clock,
         }
@@ -66,9 +68,33 @@ impl ChessMove {
             prom: Some(prom),
         }
     }
+
+    /// A move placing a piece from the player's hand on an empty square, as in Crazyhouse.
+    /// Encoded as a move from the square to itself, with the dropped piece as the promotion.
+    pub fn new_drop(square: Square, piece_type: PieceType) -> ChessMove {
+        ChessMove {
+            from: square,
+            to: square,
+            prom: Some(piece_type),
+        }
+    }
+
+    pub fn is_drop(self) -> bool {
+        self.from == self.to
+    }
+
+    /// Returns the dropped piece, if the move is a drop
+    pub fn drop_piece(self) -> Option<PieceType> {
+        if self.is_drop() {
+            self.prom
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ChessReverseNullMove {
     pub old_castling_en_passant: u8,
+    pub old_half_move_clock: u8,
 }
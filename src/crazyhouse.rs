@@ -0,0 +1,255 @@
+//! The Crazyhouse variant, where captured pieces join the capturing side's hand,
+//! and may be dropped back onto the board instead of making a regular move.
+
+use crate::bitboard::BitBoard;
+use crate::chess_board::ChessBoard;
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use crate::move_gen;
+use crate::types::{Piece, PieceType, PieceType::*, Square};
+use crate::variant::{Variant, VariantBoard, VariantReverseMove};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+
+/// The piece types that can be held in hand, in the order they are written in FEN
+const POCKET_PIECES: [PieceType; 5] = [Queen, Rook, Bishop, Knight, Pawn];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Crazyhouse {
+    // The number of pieces in each player's hand, indexed by color and then by `PieceType`
+    pockets: [[u8; 7]; 2],
+    // Pieces that were promoted from pawns. They return to the hand as pawns when captured
+    promoted: BitBoard,
+}
+
+pub type CrazyhouseBoard = VariantBoard<Crazyhouse>;
+
+pub type CrazyhouseReverseMove = VariantReverseMove<CrazyhouseUndo>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrazyhouseUndo {
+    old_promoted: BitBoard,
+    /// The piece that was dropped, if the move was a drop
+    dropped: Option<PieceType>,
+    /// The piece the capture added to the mover's hand, if any
+    pocketed: Option<PieceType>,
+}
+
+impl VariantBoard<Crazyhouse> {
+    /// Returns how many pieces of the type `color` has in hand
+    pub fn pocket(&self, color: Color, piece_type: PieceType) -> u8 {
+        self.variant.pockets[color.disc()][piece_type as usize]
+    }
+
+    /// Returns every piece on the board that was promoted from a pawn
+    pub fn promoted(&self) -> BitBoard {
+        self.variant.promoted
+    }
+
+    /// Adds the legal drops of the side to move to the list
+    pub fn generate_drops(&self, moves: &mut Vec<ChessMove>) {
+        let color = self.side_to_move();
+        // In check, a drop must block the check, and in double check no drop helps
+        let check_info = move_gen::CheckInfo::new(&self.board);
+        let targets = self.board.piece_type_bitboard(Empty) & check_info.check_mask;
+        for &piece_type in POCKET_PIECES.iter().rev() {
+            if self.pocket(color, piece_type) == 0 {
+                continue;
+            }
+            let squares = if piece_type == Pawn {
+                targets & !(BitBoard::rank(0) | BitBoard::rank(7))
+            } else {
+                targets
+            };
+            for square in squares {
+                moves.push(ChessMove::new_drop(square, piece_type));
+            }
+        }
+    }
+
+    /// Returns the piece type a move captures, if any
+    fn captured_piece(&self, mv: ChessMove) -> Option<PieceType> {
+        if !self.is_capture(mv) {
+            None
+        } else if self.board[mv.to].is_empty() {
+            // En passant
+            Some(Pawn)
+        } else {
+            Some(self.board[mv.to].piece_type())
+        }
+    }
+}
+
+impl Variant for Crazyhouse {
+    type Undo = CrazyhouseUndo;
+
+    const START_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1";
+
+    fn generate_extra_moves(board: &VariantBoard<Self>, moves: &mut Vec<ChessMove>) {
+        board.generate_drops(moves)
+    }
+
+    fn do_move(board: &mut VariantBoard<Self>, mv: ChessMove) -> (ChessReverseMove, Self::Undo) {
+        let color = board.side_to_move();
+        let old_promoted = board.variant.promoted;
+        let dropped = mv.drop_piece();
+        // A captured promoted piece goes back to being a pawn
+        let pocketed = board.captured_piece(mv).map(|piece_type| {
+            if old_promoted.get(mv.to) {
+                Pawn
+            } else {
+                piece_type
+            }
+        });
+
+        let variant = &mut board.variant;
+        match dropped {
+            Some(piece_type) => variant.pockets[color.disc()][piece_type as usize] -= 1,
+            None => {
+                let is_promoted = variant.promoted.get(mv.from) || mv.prom.is_some();
+                variant.promoted = variant.promoted.clear(mv.from).clear(mv.to);
+                if is_promoted {
+                    variant.promoted = variant.promoted.set(mv.to);
+                }
+            }
+        }
+        if let Some(piece_type) = pocketed {
+            variant.pockets[color.disc()][piece_type as usize] += 1;
+        }
+
+        let reverse_move = board.board.do_move(mv);
+
+        (
+            reverse_move,
+            CrazyhouseUndo {
+                old_promoted,
+                dropped,
+                pocketed,
+            },
+        )
+    }
+
+    fn reverse_move(
+        board: &mut VariantBoard<Self>,
+        reverse_move: ChessReverseMove,
+        undo: Self::Undo,
+    ) {
+        board.board.reverse_move(reverse_move);
+        let color = board.side_to_move();
+        let variant = &mut board.variant;
+        variant.promoted = undo.old_promoted;
+        if let Some(piece_type) = undo.dropped {
+            variant.pockets[color.disc()][piece_type as usize] += 1;
+        }
+        if let Some(piece_type) = undo.pocketed {
+            variant.pockets[color.disc()][piece_type as usize] -= 1;
+        }
+    }
+
+    /// Parses a FEN string with the pieces in hand in brackets after the board, like `[Qnp]`,
+    /// or as a ninth rank, like `/Qnp`. Promoted pieces are marked by a `~` after the piece.
+    fn from_fen(fen: &str) -> Result<VariantBoard<Self>, pgn::Error> {
+        let (board_field, rest) = fen.split_at(fen.find(' ').unwrap_or(fen.len()));
+        let (pieces_field, pocket_field) = match board_field.find('[') {
+            Some(index) if board_field.ends_with(']') => (
+                &board_field[..index],
+                &board_field[index + 1..board_field.len() - 1],
+            ),
+            Some(_) => {
+                return Err(pgn::Error::new(
+                    pgn::ErrorKind::ParseError,
+                    format!("Invalid FEN \"{}\": Pocket is not closed by ]", fen),
+                ))
+            }
+            None if board_field.matches('/').count() == 8 => {
+                board_field.split_at(board_field.rfind('/').unwrap())
+            }
+            None => (board_field, ""),
+        };
+        let pocket_field = pocket_field.trim_start_matches('/');
+
+        let mut promoted = BitBoard::empty();
+        let (mut file, mut rank) = (0u8, 0u8);
+        let mut previous = None;
+        for ch in pieces_field.chars() {
+            match ch {
+                '/' => {
+                    file = 0;
+                    rank = rank.saturating_add(1);
+                }
+                // The marker must follow a piece on the board
+                '~' if previous.is_some_and(|previous: char| previous.is_ascii_alphabetic())
+                    && (1..=8).contains(&file)
+                    && rank < 8 =>
+                {
+                    promoted = promoted.set(Square::from_ints(file - 1, rank))
+                }
+                '~' => {
+                    return Err(pgn::Error::new(
+                        pgn::ErrorKind::ParseError,
+                        format!("Invalid FEN \"{}\": Misplaced promoted marker", fen),
+                    ))
+                }
+                _ => file = file.saturating_add(ch.to_digit(10).unwrap_or(1) as u8),
+            }
+            previous = Some(ch);
+        }
+        let chess_fen = format!("{}{}", pieces_field.replace('~', ""), rest);
+        let board = ChessBoard::from_fen(&chess_fen)?;
+
+        let mut pockets = [[0; 7]; 2];
+        for ch in pocket_field.chars().filter(|&ch| ch != '-') {
+            match Piece::from_letter(ch) {
+                Some(piece) if piece.piece_type() != King => {
+                    pockets[piece.color().unwrap().disc()][piece.piece_type() as usize] += 1
+                }
+                _ => {
+                    return Err(pgn::Error::new(
+                        pgn::ErrorKind::ParseError,
+                        format!("Invalid FEN \"{}\": Illegal piece {} in pocket", fen, ch),
+                    ))
+                }
+            }
+        }
+
+        let promoted = promoted & board.occupied() & !board.piece_type_bitboard(Pawn);
+        Ok(VariantBoard::new(board, Crazyhouse { pockets, promoted }))
+    }
+
+    fn to_fen(board: &VariantBoard<Self>) -> String {
+        let chess_fen = board.board.to_fen();
+        let (pieces_field, rest) = chess_fen.split_at(chess_fen.find(' ').unwrap());
+
+        let mut fen = String::new();
+        let (mut file, mut rank) = (0, 0);
+        for ch in pieces_field.chars() {
+            fen.push(ch);
+            match ch {
+                '/' => {
+                    file = 0;
+                    rank += 1;
+                }
+                _ if ch.is_ascii_digit() => file += ch.to_digit(10).unwrap() as u8,
+                _ => {
+                    if board.promoted().get(Square::from_ints(file, rank)) {
+                        fen.push('~');
+                    }
+                    file += 1;
+                }
+            }
+        }
+
+        fen.push('[');
+        for &color in &[White, Black] {
+            for &piece_type in POCKET_PIECES.iter() {
+                for _ in 0..board.pocket(color, piece_type) {
+                    fen.push_str(&Piece::from_type_color(piece_type, color).to_string());
+                }
+            }
+        }
+        fen.push(']');
+        fen.push_str(rest);
+        fen
+    }
+}
@@ -0,0 +1,75 @@
+//! Horde, where White has 36 pawns and no king against Black's regular army.
+//! Black wins by capturing every White piece, and White wins by checkmating Black.
+
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use crate::types::{Piece, PieceType::*, Square};
+use crate::variant::{Variant, VariantBoard};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color, GameResult};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Horde;
+
+pub type HordeBoard = VariantBoard<Horde>;
+
+fn has_king(board: &VariantBoard<Horde>, color: Color) -> bool {
+    board
+        .board
+        .pos_of(Piece::from_type_color(King, color))
+        .is_some()
+}
+
+impl Variant for Horde {
+    type Undo = ();
+
+    const START_FEN: &'static str =
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1";
+
+    /// With no king to expose, every move of the side without a king is legal
+    fn king_safety(board: &VariantBoard<Self>, color: Color) -> bool {
+        has_king(board, color)
+    }
+
+    /// Adds the double pushes of White's pawns on the first rank
+    fn generate_extra_moves(board: &VariantBoard<Self>, moves: &mut Vec<ChessMove>) {
+        if board.side_to_move() != White || has_king(board, White) {
+            return;
+        }
+        for square in board.board.piece_bitboard(Pawn, White) {
+            if square.rank() != 7 {
+                continue;
+            }
+            let square_in_front = Square(square.0 - 8);
+            let square_2_in_front = Square(square.0 - 16);
+            if board.board[square_in_front].is_empty() && board.board[square_2_in_front].is_empty()
+            {
+                moves.push(ChessMove::new(square, square_2_in_front));
+            }
+        }
+    }
+
+    fn is_in_check(board: &VariantBoard<Self>) -> bool {
+        has_king(board, board.side_to_move()) && !board.board.checkers().is_empty()
+    }
+
+    /// Black wins once White has no pieces left
+    fn variant_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        if board.board.color_bitboard(White).is_empty() {
+            Some(GameResult::BlackWin)
+        } else {
+            None
+        }
+    }
+
+    fn do_move(board: &mut VariantBoard<Self>, mv: ChessMove) -> (ChessReverseMove, Self::Undo) {
+        let is_first_rank_double_push = board.board[mv.from] == Piece::from_type_color(Pawn, White)
+            && mv.from.rank() == 7
+            && mv.to.rank() == 5;
+        let reverse_move = board.board.do_move(mv);
+        // Only a double push from the second rank can be captured en passant
+        if is_first_rank_double_push {
+            board.board.set_en_passant_square(None);
+        }
+        (reverse_move, ())
+    }
+}
@@ -0,0 +1,47 @@
+//! King of the Hill, where a side also wins by moving its king to one of the four center squares
+
+use crate::bitboard::BitBoard;
+use crate::chess_board::Termination;
+use crate::types::{PieceType::*, Square};
+use crate::variant::{Variant, VariantBoard};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::GameResult;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KingOfTheHill;
+
+pub type KingOfTheHillBoard = VariantBoard<KingOfTheHill>;
+
+/// Returns the d4, e4, d5 and e5 squares
+pub fn hill() -> BitBoard {
+    [(3, 3), (4, 3), (3, 4), (4, 4)]
+        .iter()
+        .fold(BitBoard::empty(), |hill, &(file, rank)| {
+            hill.set(Square::from_ints(file, rank))
+        })
+}
+
+impl Variant for KingOfTheHill {
+    type Undo = ();
+
+    fn variant_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        if !(board.board.piece_bitboard(King, White) & hill()).is_empty() {
+            Some(GameResult::WhiteWin)
+        } else if !(board.board.piece_bitboard(King, Black) & hill()).is_empty() {
+            Some(GameResult::BlackWin)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a win for a king on the hill, or the result of the game under the standard rules.
+    /// A bare king can always walk to the hill, so the game is never drawn for lack of material.
+    fn game_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        Self::variant_result(board).or_else(|| {
+            board
+                .board
+                .termination_with_material_rule(false)
+                .map(Termination::result)
+        })
+    }
+}
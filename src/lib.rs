@@ -1,7 +1,24 @@
+pub mod antichess;
+pub mod atomic;
+pub mod attacks;
+pub mod bitboard;
 pub mod chess_board;
+pub mod chess_game;
 pub mod chess_move;
+pub mod crazyhouse;
+pub mod horde;
+pub mod king_of_the_hill;
+pub mod magic;
 pub mod move_gen;
+pub mod move_list;
+pub mod pgn_reader;
+pub mod pgn_writer;
+pub mod polyglot;
+pub mod racing_kings;
+pub mod three_check;
 pub mod types;
+pub mod variant;
+pub mod zobrist;
 #[cfg(test)]
 mod tests;
 
@@ -0,0 +1,279 @@
+//! Magic bitboard lookup tables for sliding piece attacks.
+//!
+//! The relevant blockers for a square are multiplied by a magic number, and the high bits of the
+//! product index a table of precomputed attack sets. The tables are built on first use.
+//!
+//! With the `pext` cargo feature enabled, and when compiling for an x86_64 target with BMI2
+//! (e.g. with `-C target-cpu=native`), the same tables are indexed with the `pext` instruction instead.
+
+use crate::attacks::{sliding_attacks, BISHOP_DIRECTIONS, ROOK_DIRECTIONS};
+use crate::bitboard::BitBoard;
+use crate::types::Square;
+use std::sync::OnceLock;
+
+/// Magic multipliers for rook attacks, indexed by square
+pub const ROOK_MAGICS: [u64; 64] = [
+    0x0080_0080_4000_2018,
+    0x0040_1000_4000_2001,
+    0x0900_0d00_1020_0040,
+    0x8080_0800_1000_8004,
+    0xa280_0800_0234_0080,
+    0x2500_0500_2400_0208,
+    0x0280_0100_0080_0200,
+    0x1100_1443_8022_0100,
+    0xa920_8004_8c20_4002,
+    0x0802_8040_0320_0080,
+    0x0108_8020_0010_0089,
+    0x8060_8008_0080_1002,
+    0xa002_0008_2200_0410,
+    0x950a_0010_0200_0824,
+    0x1202_0004_0801_0200,
+    0x0295_0008_408a_0100,
+    0x40a0_a180_0081_4000,
+    0x0080_8480_2000_4011,
+    0x0800_8280_1000_2000,
+    0x0500_4200_1020_0a00,
+    0x0008_8180_0400_0802,
+    0x0000_8080_0400_0200,
+    0x0000_0400_0102_0810,
+    0x0081_1200_0044_2081,
+    0x0522_4001_8000_2090,
+    0x2800_2000_4040_1000,
+    0x4020_04a1_8010_0481,
+    0x0880_4202_0020_0810,
+    0x000a_0400_8080_0800,
+    0x080a_0004_0400_1020,
+    0x0000_0104_0082_0810,
+    0x4000_d082_0000_4c09,
+    0x0100_8040_0080_0020,
+    0xcc01_0280_2600_4200,
+    0x0081_0020_0100_4010,
+    0x0000_8008_0080_1000,
+    0x0009_8004_0180_2800,
+    0x0002_0009_0200_0410,
+    0x0000_1801_4400_1022,
+    0x0000_8100_c600_2884,
+    0x1200_8000_4000_8024,
+    0x2000_2000_5004_4000,
+    0x0090_8822_0042_0010,
+    0x6a18_1020_0a02_0040,
+    0x0480_0400_0800_8080,
+    0x0206_0011_0816_0014,
+    0xc010_0402_0001_0100,
+    0x0000_0101_8046_000c,
+    0x00b0_4008_8004_2080,
+    0x0040_0020_1008_0220,
+    0x0020_0042_2811_0100,
+    0x0150_0008_0400_4140,
+    0x0028_0080_0400_0980,
+    0x0801_0008_0400_0300,
+    0x2c80_2102_0890_0400,
+    0x0000_0401_3040_8200,
+    0x4001_4091_0025_8001,
+    0x0005_0040_0288_3021,
+    0x0190_41e0_0300_1019,
+    0x8000_2009_0004_1001,
+    0x4021_0010_0204_0801,
+    0x0011_0004_0008_0201,
+    0x1600_0102_1040_8804,
+    0x2010_8100_8400_3042,
+];
+
+/// Magic multipliers for bishop attacks, indexed by square
+pub const BISHOP_MAGICS: [u64; 64] = [
+    0x0032_4828_0081_8200,
+    0x0819_0228_2045_0000,
+    0x4242_1082_0488_0008,
+    0x8044_4040_8041_0224,
+    0x1407_1040_0008_0100,
+    0x1b01_1002_1000_4000,
+    0x0b0c_0888_8410_10c1,
+    0x08a0_1082_0110_4020,
+    0x0000_3888_654c_0410,
+    0x0010_2202_0252_0a00,
+    0x4003_0484_0082_0001,
+    0x1007_0220_8200_0002,
+    0x3c22_0404_2004_0a18,
+    0x3440_5088_2008_0030,
+    0x1204_2080_9008_2100,
+    0x0000_9305_0101_2000,
+    0x0010_8420_02d0_0100,
+    0x0202_0004_0448_4208,
+    0x0008_0001_0041_0602,
+    0x0088_0004_0420_0800,
+    0xa004_1002_0202_0232,
+    0x0004_2001_00a0_1002,
+    0x0201_0002_0802_0200,
+    0x2022_0100_2202_0200,
+    0x8010_0410_1004_10b0,
+    0x3010_7048_4801_1100,
+    0x2000_3800_1004_8320,
+    0x8068_0800_0020_2120,
+    0x0001_0100_8010_4000,
+    0x4808_0041_2080_6000,
+    0x5000_8210_0082_3008,
+    0x0004_0100_0050_4224,
+    0x0088_0440_1004_0808,
+    0x0812_0220_0003_2814,
+    0xa000_2808_0011_0202,
+    0x2882_2008_0201_0105,
+    0x0508_0824_0002_4100,
+    0x8001_0102_0003_0800,
+    0x4001_260e_0001_9820,
+    0x0c02_0403_0850_7180,
+    0x0880_8844_4100_9000,
+    0x20a8_4202_a001_1000,
+    0x0001_0410_820c_1000,
+    0x4000_0020_1800_0108,
+    0x0080_0911_2400_4a00,
+    0x0409_0103_0201_0700,
+    0x0088_1288_0204_0040,
+    0x1801_0104_0880_1100,
+    0x0184_0101_9011_0800,
+    0x0000_4042_0821_0000,
+    0x0000_1209_0888_0000,
+    0x9006_1800_2088_4020,
+    0x9000_0040_0488_410a,
+    0x0410_2184_0102_0100,
+    0x0004_d004_2800_8100,
+    0x0002_0214_0400_8400,
+    0x0911_4101_5022_2026,
+    0x0300_2020_8804_1040,
+    0x0100_a061_2412_2800,
+    0x001c_0420_0042_0200,
+    0x0004_0182_0803_0400,
+    0x0044_0404_0508_0200,
+    0x0408_2020_0402_b680,
+    0x0204_2004_1102_0410,
+];
+
+#[derive(Clone, Copy, Default)]
+struct MagicEntry {
+    mask: BitBoard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl MagicEntry {
+    #[cfg(not(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2")))]
+    #[inline]
+    fn index(&self, occupied: BitBoard) -> usize {
+        let blockers = (occupied & self.mask).board;
+        self.offset + (blockers.wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    #[cfg(all(feature = "pext", target_arch = "x86_64", target_feature = "bmi2"))]
+    #[inline]
+    fn index(&self, occupied: BitBoard) -> usize {
+        // Safe because the bmi2 target feature is enabled at compile time
+        self.offset
+            + unsafe { std::arch::x86_64::_pext_u64(occupied.board, self.mask.board) } as usize
+    }
+}
+
+struct MagicTables {
+    rook_entries: [MagicEntry; 64],
+    bishop_entries: [MagicEntry; 64],
+    attacks: Vec<BitBoard>,
+}
+
+static TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+fn tables() -> &'static MagicTables {
+    TABLES.get_or_init(MagicTables::new)
+}
+
+impl MagicTables {
+    fn new() -> Self {
+        let mut attacks = Vec::with_capacity(107_648);
+        let rook_entries =
+            Self::init_piece(&mut attacks, &ROOK_MAGICS, rook_mask, &ROOK_DIRECTIONS);
+        let bishop_entries = Self::init_piece(
+            &mut attacks,
+            &BISHOP_MAGICS,
+            bishop_mask,
+            &BISHOP_DIRECTIONS,
+        );
+        MagicTables {
+            rook_entries,
+            bishop_entries,
+            attacks,
+        }
+    }
+
+    fn init_piece(
+        attacks: &mut Vec<BitBoard>,
+        magics: &[u64; 64],
+        mask: fn(Square) -> BitBoard,
+        directions: &[(i8, i8)],
+    ) -> [MagicEntry; 64] {
+        let mut entries = [MagicEntry::default(); 64];
+        for (i, entry) in entries.iter_mut().enumerate() {
+            let square = Square(i as u8);
+            let mask = mask(square);
+            let bits = mask.popcount();
+            *entry = MagicEntry {
+                mask,
+                magic: magics[i],
+                shift: 64 - bits,
+                offset: attacks.len(),
+            };
+            attacks.resize(attacks.len() + (1 << bits), BitBoard::empty());
+
+            // Enumerate every subset of the mask, using the Carry-Rippler trick
+            let mut blockers = BitBoard::empty();
+            loop {
+                let index = entry.index(blockers);
+                attacks[index] = sliding_attacks(square, blockers, directions);
+                blockers = BitBoard::from_u64(blockers.board.wrapping_sub(mask.board) & mask.board);
+                if blockers.is_empty() {
+                    break;
+                }
+            }
+        }
+        entries
+    }
+}
+
+/// Returns the squares whose occupancy can affect a rook's attacks from `square`.
+/// That is every square it attacks on an empty board, except the last square of each ray.
+pub fn rook_mask(square: Square) -> BitBoard {
+    relevant_blockers(square, &ROOK_DIRECTIONS)
+}
+
+/// Returns the squares whose occupancy can affect a bishop's attacks from `square`.
+/// That is every square it attacks on an empty board, except the last square of each ray.
+pub fn bishop_mask(square: Square) -> BitBoard {
+    relevant_blockers(square, &BISHOP_DIRECTIONS)
+}
+
+fn relevant_blockers(square: Square, directions: &[(i8, i8)]) -> BitBoard {
+    let mut mask = BitBoard::empty();
+    for &direction in directions {
+        let ray = sliding_attacks(square, BitBoard::empty(), &[direction]);
+        // The ray's last square lies on the edge, and is furthest away from the origin square
+        let edge_square = ray.squares().max_by_key(|target| {
+            (target.file() as i8 - square.file() as i8).abs()
+                + (target.rank() as i8 - square.rank() as i8).abs()
+        });
+        if let Some(edge_square) = edge_square {
+            mask |= ray.clear(edge_square);
+        }
+    }
+    mask
+}
+
+/// Returns the squares a rook on `square` attacks, using the magic lookup tables
+#[inline]
+pub fn rook_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    let tables = tables();
+    tables.attacks[tables.rook_entries[square.0 as usize].index(occupied)]
+}
+
+/// Returns the squares a bishop on `square` attacks, using the magic lookup tables
+#[inline]
+pub fn bishop_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    let tables = tables();
+    tables.attacks[tables.bishop_entries[square.0 as usize].index(occupied)]
+}
@@ -1,5 +1,8 @@
-use crate::chess_board::ChessBoard;
+use crate::attacks;
+use crate::bitboard::BitBoard;
+use crate::chess_board::{Castling, ChessBoard};
 use crate::chess_move::ChessMove;
+use crate::move_list::MoveList;
 use board_game_traits::board::Color::*;
 
 use board_game_traits::board::Board;
@@ -8,672 +11,832 @@ use std::cmp::Ordering;
 
 use crate::types::{Piece, PieceType, PieceType::*, Square};
 
-#[inline(never)]
-pub fn all_legal_moves(board: &ChessBoard) -> (Vec<ChessMove>, Vec<ChessMove>) {
-    let mut mut_board = board.clone();
-    if board.half_move_clock > 100 {
-        // Draw by 50-move rule
-        return (vec![], vec![]);
-    }
-    let mut winning_moves = Vec::new();
-    let mut active_moves = Vec::new();
-    let mut quiet_moves = Vec::new();
-    let king_pos = king_pos(board);
-    let is_in_check = is_attacked(board, king_pos);
-    for i in 0..64 {
-        if board[Square(i)].color() == Some(board.to_move) {
-            legal_moves_for_piece(
-                &mut mut_board,
-                Square(i),
-                &mut winning_moves,
-                &mut active_moves,
-                &mut quiet_moves,
-                is_in_check,
-                king_pos,
-            );
-        }
-    }
-    winning_moves.append(&mut active_moves);
-    (winning_moves, quiet_moves)
+/// Checks and pins against the side to move's king, computed once per position.
+/// Every move generated from it is legal, without needing to play the move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckInfo {
+    pub king_pos: Square,
+    /// Every enemy piece giving check. Has two pieces in double check
+    pub checkers: BitBoard,
+    /// The squares a non-king piece must move to, to resolve any check.
+    /// That is the checker and the squares between it and the king in single check,
+    /// no squares in double check, and every square when not in check
+    pub check_mask: BitBoard,
+    /// Every friendly piece that is pinned to the king
+    pub pinned: BitBoard,
 }
 
-/// Adds all the legal moves for the piece in this position, to the input vector
-/// Takes in the king position for the moving player, and whether they are currently in check,
-/// to speed up the move generation
-#[inline(never)]
-pub fn legal_moves_for_piece(
-    board: &mut ChessBoard,
-    square: Square,
-    winning_moves: &mut Vec<ChessMove>,
-    active_moves: &mut Vec<ChessMove>,
-    quiet_moves: &mut Vec<ChessMove>,
-    is_in_check: bool,
-    king_pos: Square,
-) {
-    let piece = board[square].piece_type();
-    match piece {
-        King => legal_moves_for_king(board, square, quiet_moves),
-
-        Queen => {
-            add_moves_diagonally(
-                board,
-                square,
-                winning_moves,
-                active_moves,
-                quiet_moves,
-                king_pos,
-                is_in_check,
-            );
-            add_straight_moves(
-                board,
-                square,
-                winning_moves,
-                active_moves,
-                quiet_moves,
-                king_pos,
-                is_in_check,
-            );
+impl CheckInfo {
+    pub fn new(board: &ChessBoard) -> Self {
+        let color = board.to_move;
+        let king_pos = king_pos(board);
+        let occupied = board.occupied();
+        let checkers = attackers(board, king_pos, color, occupied);
+
+        let check_mask = match checkers.first_square() {
+            None => BitBoard::full(),
+            Some(_) if checkers.has_several() => BitBoard::empty(),
+            Some(checker) => attacks::between(king_pos, checker).set(checker),
+        };
+
+        // Enemy sliders that would attack the king if not for the pieces in between
+        let enemy_pieces = board.color_bitboard(!color);
+        let snipers = ((attacks::rook_attacks(king_pos, enemy_pieces)
+            & (board.piece_type_bitboard(Rook) | board.piece_type_bitboard(Queen)))
+            | (attacks::bishop_attacks(king_pos, enemy_pieces)
+                & (board.piece_type_bitboard(Bishop) | board.piece_type_bitboard(Queen))))
+            & enemy_pieces;
+
+        let mut pinned = BitBoard::empty();
+        for sniper in snipers {
+            let blockers = attacks::between(king_pos, sniper) & occupied;
+            if !blockers.has_several() {
+                pinned |= blockers & board.color_bitboard(color);
+            }
         }
-        Rook => {
-            add_straight_moves(
-                board,
-                square,
-                winning_moves,
-                active_moves,
-                quiet_moves,
-                king_pos,
-                is_in_check,
-            );
+
+        CheckInfo {
+            king_pos,
+            checkers,
+            check_mask,
+            pinned,
         }
-        Bishop => {
-            add_moves_diagonally(
-                board,
-                square,
-                winning_moves,
-                active_moves,
-                quiet_moves,
-                king_pos,
-                is_in_check,
-            );
+    }
+
+    pub fn is_in_check(&self) -> bool {
+        !self.checkers.is_empty()
+    }
+
+    /// Returns the squares a non-king piece on `square` may legally move to,
+    /// according to the check mask and its pin ray
+    pub fn legal_targets(&self, square: Square) -> BitBoard {
+        if self.pinned.get(square) {
+            self.check_mask & attacks::line(self.king_pos, square)
+        } else {
+            self.check_mask
         }
-        Knight => legal_moves_for_knight(
-            board,
-            square,
-            winning_moves,
-            active_moves,
-            quiet_moves,
-            is_in_check,
-            king_pos,
-        ),
-
-        Pawn => legal_moves_for_pawn(
-            board,
-            square,
-            winning_moves,
-            active_moves,
-            quiet_moves,
-            is_in_check,
-            king_pos,
-        ),
+    }
+}
+
+/// The kinds of moves produced by a stage of the move generator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    /// Captures, including en passant, and all promotions
+    Captures,
+    /// All other moves, including castling
+    Quiets,
+    All,
+}
+
+impl Stage {
+    fn has_captures(self) -> bool {
+        self != Stage::Quiets
+    }
 
-        Empty => (),
+    fn has_quiets(self) -> bool {
+        self != Stage::Captures
     }
 }
 
-#[inline(never)]
-fn legal_moves_for_king(board: &mut ChessBoard, square: Square, moves: &mut Vec<ChessMove>) {
-    let file = square.file() as i8;
-    let rank = square.rank() as i8;
-    // If the king and the two castling squares are not in check, castling is allowed
-    // There must be no pieces between the castling pieces
-
-    // Kingside castling
-    if board.can_castle_kingside(board.to_move) {
-        let mut can_castle_here = !is_attacked(board, square);
-
-        // Check that the two squares are empty and not in check
-        for n in &[1, 2] {
-            debug_assert!(
-                file == 4,
-                format!("Error: King tried to castle from {} on:{}.", square, board)
-            );
-            let square_checked = Square(square.0 + n);
-            if !board.piece_at(square_checked).is_empty() || is_attacked(board, square_checked) {
-                can_castle_here = false;
-            }
-        }
-        if can_castle_here {
-            moves.push(ChessMove::new(square, Square(square.0 + 2)));
+/// Moves from the generator, with captures sorted by whether they win, trade or lose material
+#[derive(Default)]
+struct StagedMoves {
+    winning: MoveList,
+    equal: MoveList,
+    losing: MoveList,
+    quiet: MoveList,
+    // If set, captures are not evaluated, and are all kept in `winning` with the promotions
+    unsorted: bool,
+}
+
+impl StagedMoves {
+    /// Staged moves for callers that do not need the captures ordered
+    fn unsorted() -> Self {
+        StagedMoves {
+            unsorted: true,
+            ..StagedMoves::default()
         }
     }
 
-    // Queenside castling
-    if board.can_castle_queenside(board.to_move) {
-        let mut can_castle_here = !is_attacked(board, square);
+    fn is_empty(&self) -> bool {
+        self.winning.is_empty()
+            && self.equal.is_empty()
+            && self.losing.is_empty()
+            && self.quiet.is_empty()
+    }
 
-        // Check that the two squares are empty and not in check
-        for n in &[1, 2] {
-            debug_assert!(file == 4, format!("Error: File is {}.", file));
-            let square_checked = Square(square.0 - n);
-            if !board.piece_at(square_checked).is_empty() || is_attacked(board, square_checked) {
-                can_castle_here = false;
-            }
+    /// Sorts a capture by its static exchange evaluation
+    fn push_capture(&mut self, board: &ChessBoard, mv: ChessMove) {
+        if self.unsorted {
+            self.winning.push(mv);
+            return;
         }
-        // Check that the knight-square is empty
-        if !board.piece_at(Square(square.0 - 3)).is_empty() {
-            can_castle_here = false;
+        match see(board, mv).cmp(&0) {
+            Ordering::Less => self.losing.push(mv),
+            Ordering::Equal => self.equal.push(mv),
+            Ordering::Greater => self.winning.push(mv),
         }
-        if can_castle_here {
-            moves.push(ChessMove::new(square, Square(square.0 - 2)));
+    }
+}
+
+/// Generates the legal moves of one stage, for every piece of the side to move
+fn generate_stage(
+    board: &ChessBoard,
+    stage: Stage,
+    check_info: &CheckInfo,
+    moves: &mut StagedMoves,
+) {
+    let movers = if check_info.checkers.has_several() {
+        // Only the king can move in double check
+        board.piece_bitboard(King, board.to_move)
+    } else {
+        board.color_bitboard(board.to_move)
+    };
+    for square in movers {
+        moves_for_piece(board, square, stage, check_info, moves);
+    }
+}
+
+/// Generates all legal moves in the position, without allocating.
+/// Captures and promotions that win or trade material are added to `active_moves`,
+/// ordered with winning captures first. All other moves are added to `quiet_moves`.
+#[inline(never)]
+pub fn all_legal_moves(
+    board: &ChessBoard,
+    active_moves: &mut MoveList,
+    quiet_moves: &mut MoveList,
+) {
+    let mut moves = StagedMoves::default();
+    generate_stage(board, Stage::All, &CheckInfo::new(board), &mut moves);
+    active_moves.extend_from_slice(&moves.winning);
+    active_moves.extend_from_slice(&moves.equal);
+    quiet_moves.extend_from_slice(&moves.losing);
+    quiet_moves.extend_from_slice(&moves.quiet);
+}
+
+/// Generates all legal moves in the position into `moves`, without allocating.
+/// Captures and promotions are generated first, in no particular order.
+pub fn generate_legal_into(board: &ChessBoard, moves: &mut MoveList) {
+    let mut staged_moves = StagedMoves::unsorted();
+    generate_stage(board, Stage::All, &CheckInfo::new(board), &mut staged_moves);
+    moves.extend_from_slice(&staged_moves.winning);
+    moves.extend_from_slice(&staged_moves.quiet);
+}
+
+/// Returns whether the side to move has any legal move.
+/// Stops at the first piece that can move, instead of generating every move.
+pub fn has_legal_moves(board: &ChessBoard) -> bool {
+    let check_info = CheckInfo::new(board);
+    let mut moves = StagedMoves::unsorted();
+    moves_for_piece(
+        board,
+        check_info.king_pos,
+        Stage::All,
+        &check_info,
+        &mut moves,
+    );
+    if !moves.is_empty() {
+        return true;
+    }
+    if check_info.checkers.has_several() {
+        // Only the king can move in double check
+        return false;
+    }
+    board
+        .color_bitboard(board.to_move)
+        .clear(check_info.king_pos)
+        .squares()
+        .any(|square| {
+            moves_for_piece(board, square, Stage::All, &check_info, &mut moves);
+            !moves.is_empty()
+        })
+}
+
+/// Generates the legal captures and promotions.
+/// Captures that win material come first, then trades, then captures that lose material.
+pub fn generate_captures(board: &ChessBoard, moves: &mut MoveList) {
+    let mut staged_moves = StagedMoves::default();
+    generate_stage(
+        board,
+        Stage::Captures,
+        &CheckInfo::new(board),
+        &mut staged_moves,
+    );
+    moves.extend_from_slice(&staged_moves.winning);
+    moves.extend_from_slice(&staged_moves.equal);
+    moves.extend_from_slice(&staged_moves.losing);
+}
+
+/// Generates the legal captures and promotions that win or trade material,
+/// with winning captures first
+pub fn generate_active_moves(board: &ChessBoard, moves: &mut MoveList) {
+    let mut staged_moves = StagedMoves::default();
+    generate_stage(
+        board,
+        Stage::Captures,
+        &CheckInfo::new(board),
+        &mut staged_moves,
+    );
+    moves.extend_from_slice(&staged_moves.winning);
+    moves.extend_from_slice(&staged_moves.equal);
+}
+
+/// Generates the legal moves that are neither captures nor promotions, including castling
+pub fn generate_quiets(board: &ChessBoard, moves: &mut MoveList) {
+    let mut staged_moves = StagedMoves::default();
+    generate_stage(
+        board,
+        Stage::Quiets,
+        &CheckInfo::new(board),
+        &mut staged_moves,
+    );
+    moves.extend_from_slice(&staged_moves.quiet);
+}
+
+/// Generates every legal move out of check, captures first.
+/// Must only be called when the side to move is in check.
+pub fn generate_evasions(board: &ChessBoard, moves: &mut MoveList) {
+    let check_info = CheckInfo::new(board);
+    debug_assert!(
+        check_info.is_in_check(),
+        "Generated evasions without being in check on\n{}",
+        board
+    );
+    let mut staged_moves = StagedMoves::default();
+    generate_stage(board, Stage::All, &check_info, &mut staged_moves);
+    moves.extend_from_slice(&staged_moves.winning);
+    moves.extend_from_slice(&staged_moves.equal);
+    moves.extend_from_slice(&staged_moves.losing);
+    moves.extend_from_slice(&staged_moves.quiet);
+}
+
+/// Generates the quiet moves that give check, directly or by discovery, for quiescence search
+pub fn generate_quiet_checks(board: &ChessBoard, moves: &mut MoveList) {
+    let mut staged_moves = StagedMoves::default();
+    generate_stage(
+        board,
+        Stage::Quiets,
+        &CheckInfo::new(board),
+        &mut staged_moves,
+    );
+    let check_squares = CheckSquares::new(board);
+    for &mv in staged_moves.quiet.iter() {
+        if check_squares.gives_check(board, mv) {
+            moves.push(mv);
         }
     }
+}
 
-    for i in -1..2 {
-        for j in -1..2 {
-            if file + i < 0 || file + i >= 8 || rank + j < 0 || rank + j >= 8 || (j == 0 && i == 0)
-            {
+/// Generates every move that follows the movement rules of the pieces,
+/// whether or not it leaves the king in check. Castling is not included.
+/// Used by variants with their own rules for which moves are legal.
+pub fn generate_pseudo_legal(board: &ChessBoard, moves: &mut MoveList) {
+    let color = board.to_move;
+    let own_pieces = board.color_bitboard(color);
+    let occupied = board.occupied();
+    for square in own_pieces {
+        let targets = match board[square].piece_type() {
+            Pawn => {
+                pseudo_legal_pawn_moves(board, square, moves);
                 continue;
             }
-            let new_pos = Square(((rank + j) * 8 + file + i) as u8);
-
-            // Check that the square is not occupied by a friendly piece
-            let mv = ChessMove::new(square, new_pos);
-            if board[new_pos].is_empty() {
-                let old_piece = board[square];
-                board[square] = Piece::empty();
-                if !is_attacked(board, new_pos) {
-                    moves.push(mv);
-                }
-                board[square] = old_piece;
-            } else if board[new_pos].color().unwrap() != board.to_move {
-                add_if_legal_simple(board, mv, moves);
-            }
+            Knight => attacks::knight_attacks(square),
+            Bishop => attacks::bishop_attacks(square, occupied),
+            Rook => attacks::rook_attacks(square, occupied),
+            Queen => attacks::queen_attacks(square, occupied),
+            King => attacks::king_attacks(square),
+            Empty => unreachable!(),
+        };
+        for target in targets & !own_pieces {
+            moves.push(ChessMove::new(square, target));
         }
     }
 }
 
-#[inline(never)]
-fn legal_moves_for_knight(
-    board: &ChessBoard,
-    square: Square,
-    winning_moves: &mut Vec<ChessMove>,
-    active_moves: &mut Vec<ChessMove>,
-    quiet_moves: &mut Vec<ChessMove>,
-    is_in_check: bool,
-    king_pos: Square,
-) {
-    let file = square.file() as i8;
-    let rank = square.rank() as i8;
-
-    for &(i, j) in &[
-        (-2, -1),
-        (-2, 1),
-        (-1, -2),
-        (-1, 2),
-        (1, -2),
-        (1, 2),
-        (2, -1),
-        (2, 1),
-    ] {
-        if file + i < 0 || file + i >= 8 || rank + j < 0 || rank + j >= 8 {
-            continue;
+fn pseudo_legal_pawn_moves(board: &ChessBoard, square: Square, moves: &mut MoveList) {
+    let color = board.to_move;
+    let (start_rank, prom_rank) = if color == White { (6, 1) } else { (1, 6) };
+    let mut push = |target: Square| {
+        if square.rank() == prom_rank {
+            for &piece_type in &[Queen, Rook, Bishop, Knight] {
+                moves.push(ChessMove::new_prom(square, target, piece_type));
+            }
+        } else {
+            moves.push(ChessMove::new(square, target));
         }
-        let new_pos = Square(((rank + j) * 8 + file + i) as u8);
+    };
 
-        let piece_to = board[new_pos];
-        let mv = ChessMove::new(square, new_pos);
+    let mut capture_targets = board.color_bitboard(!color);
+    if let Some(ep_square) = board.en_passant_square() {
+        capture_targets = capture_targets.set(ep_square);
+    }
+    for target in attacks::pawn_attacks(color, square) & capture_targets {
+        push(target);
+    }
 
-        if board[new_pos].color() != Some(board.to_move) {
-            match piece_to
-                .value()
-                .abs()
-                .partial_cmp(&board[square].value().abs())
-                .unwrap()
-            {
-                Ordering::Less => add_if_legal(board, mv, quiet_moves, king_pos, is_in_check),
-                Ordering::Equal => add_if_legal(board, mv, active_moves, king_pos, is_in_check),
-                Ordering::Greater => add_if_legal(board, mv, winning_moves, king_pos, is_in_check),
+    let square_in_front = pawn_push(color, square);
+    if board[square_in_front].is_empty() {
+        push(square_in_front);
+        if square.rank() == start_rank {
+            let square_2_in_front = pawn_push(color, square_in_front);
+            if board[square_2_in_front].is_empty() {
+                moves.push(ChessMove::new(square, square_2_in_front));
             }
         }
     }
 }
-
+/// Adds all the legal moves for the piece in this position, to the input lists
+/// Takes in the checks and pins for the moving player, so that only legal moves are generated
 #[inline(never)]
-fn legal_moves_for_pawn(
+pub fn legal_moves_for_piece(
     board: &ChessBoard,
     square: Square,
-    winning_moves: &mut Vec<ChessMove>,
-    _active_moves: &mut Vec<ChessMove>,
-    quiet_moves: &mut Vec<ChessMove>,
-    is_in_check: bool,
-    king_pos: Square,
+    winning_moves: &mut MoveList,
+    active_moves: &mut MoveList,
+    quiet_moves: &mut MoveList,
+    check_info: &CheckInfo,
 ) {
-    // Helper variables
-    let file = square.file() as i8;
-    let rank = square.rank() as i8;
-    let pos = square.0 as i8;
+    let mut moves = StagedMoves::default();
+    moves_for_piece(board, square, Stage::All, check_info, &mut moves);
+    winning_moves.extend_from_slice(&moves.winning);
+    active_moves.extend_from_slice(&moves.equal);
+    quiet_moves.extend_from_slice(&moves.losing);
+    quiet_moves.extend_from_slice(&moves.quiet);
+}
 
-    let (start_rank, prom_rank, direction) = if board.to_move == White {
-        (6, 1, -1)
-    } else {
-        (1, 6, 1)
+fn moves_for_piece(
+    board: &ChessBoard,
+    square: Square,
+    stage: Stage,
+    check_info: &CheckInfo,
+    moves: &mut StagedMoves,
+) {
+    let targets = match board[square].piece_type() {
+        King => return legal_moves_for_king(board, square, stage, moves),
+        Pawn => return legal_moves_for_pawn(board, square, stage, check_info, moves),
+        Queen => attacks::queen_attacks(square, board.occupied()),
+        Rook => attacks::rook_attacks(square, board.occupied()),
+        Bishop => attacks::bishop_attacks(square, board.occupied()),
+        Knight => attacks::knight_attacks(square),
+        Empty => BitBoard::empty(),
     };
-    debug_assert!(rank > 0 && rank < 7);
+    let targets = targets & check_info.legal_targets(square);
 
-    // Checks if there are any pieces available for capture to the left,
-    // including en passant capture
-    if file > 0 {
-        let take_square = Square((pos + direction * 8) as u8 - 1);
-        if !board[take_square].is_empty() && board[take_square].color().unwrap() != board.to_move {
-            if rank == prom_rank {
-                for piece_type in &[Queen, Rook, Bishop, Knight] {
-                    let mv = ChessMove::new_prom(square, take_square, *piece_type);
-                    add_if_legal(board, mv, winning_moves, king_pos, is_in_check);
-                }
-            } else {
-                let mv = ChessMove::new(square, take_square);
-                if board[take_square].piece_type() == Pawn {
-                    add_if_legal(board, mv, quiet_moves, king_pos, is_in_check);
-                } else {
-                    add_if_legal(board, mv, winning_moves, king_pos, is_in_check);
-                }
-            }
+    if stage.has_captures() {
+        for target in targets & board.color_bitboard(!board.to_move) {
+            moves.push_capture(board, ChessMove::new(square, target));
         }
-        if board.en_passant_square().is_some() && take_square == board.en_passant_square().unwrap()
-        {
-            let mv = ChessMove::new(square, take_square);
-            add_if_legal_simple(board, mv, quiet_moves);
+    }
+    if stage.has_quiets() {
+        for target in targets & board.piece_type_bitboard(Empty) {
+            moves.quiet.push(ChessMove::new(square, target));
         }
     }
-    // Ditto, but to the right
-    if file < 7 {
-        let take_square = Square((pos + direction * 8) as u8 + 1);
-        let piece = board[take_square].piece_type();
-        if piece != Empty && board[take_square].color().unwrap() != board.to_move {
-            if rank == prom_rank {
-                for piece_type in &[Queen, Rook, Bishop, Knight] {
-                    let mv = ChessMove::new_prom(square, take_square, *piece_type);
-                    add_if_legal(board, mv, winning_moves, king_pos, is_in_check);
-                }
+}
+
+#[inline(never)]
+fn legal_moves_for_king(board: &ChessBoard, square: Square, stage: Stage, moves: &mut StagedMoves) {
+    let color = board.to_move;
+    let occupied = board.occupied();
+
+    // Remove the king from the occupancy, so that it cannot hide from a slider on its own ray
+    let occupied_without_king = occupied.clear(square);
+    let mut targets = BitBoard::empty();
+    if stage.has_captures() {
+        targets |= board.color_bitboard(!color);
+    }
+    if stage.has_quiets() {
+        targets |= board.piece_type_bitboard(Empty);
+    }
+    for target in attacks::king_attacks(square) & targets {
+        if attackers(board, target, color, occupied_without_king).is_empty() {
+            if board[target].is_empty() {
+                moves.quiet.push(ChessMove::new(square, target));
             } else {
-                let mv = ChessMove::new(square, take_square);
-                if board[take_square].piece_type() == Pawn {
-                    add_if_legal(board, mv, quiet_moves, king_pos, is_in_check);
-                } else {
-                    add_if_legal(board, mv, winning_moves, king_pos, is_in_check);
-                }
+                moves.push_capture(board, ChessMove::new(square, target));
             }
         }
-        if board.en_passant_square().is_some() && take_square == board.en_passant_square().unwrap()
-        {
-            let mv = ChessMove::new(square, take_square);
-            add_if_legal_simple(board, mv, quiet_moves);
-        }
     }
 
-    //Checks if the pawn can walk forward one or two squares, promote
-    let square_in_front = Square::from_ints(file as u8, (rank + direction) as u8);
-
-    if board.piece_at(square_in_front).is_empty() {
-        if rank == prom_rank {
-            for piece_type in &[Queen, Rook, Bishop, Knight] {
-                let mv = ChessMove::new_prom(square, square_in_front, *piece_type);
-                add_if_legal(board, mv, winning_moves, king_pos, is_in_check);
-            }
-        } else {
-            let mv = ChessMove::new(square, square_in_front);
-            add_if_legal(board, mv, quiet_moves, king_pos, is_in_check);
-        }
-        if rank == start_rank {
-            let square_2_in_front = Square::from_ints(file as u8, (rank + direction * 2) as u8);
-            let mv = ChessMove::new(square, square_2_in_front);
-            if board.piece_at(square_2_in_front).is_empty() {
-                add_if_legal(board, mv, quiet_moves, king_pos, is_in_check);
-            }
+    if !stage.has_quiets() {
+        return;
+    }
+    for &kingside in &[true, false] {
+        let (mv, castling) = match unobstructed_castling(board, kingside) {
+            Some(castling) => castling,
+            None => continue,
+        };
+        // The king may not castle out of, through or into check.
+        // In Chess960, the castling rook may have been shielding the king's destination
+        let occupied_without_castling_pieces =
+            occupied.clear(castling.king_from).clear(castling.rook_from);
+        if castling_king_path(castling)
+            .squares()
+            .all(|sq| attackers(board, sq, color, occupied_without_castling_pieces).is_empty())
+        {
+            moves.quiet.push(mv);
         }
     }
 }
 
-/// Checks that the move does not put the player in check
-/// Does not work in some special cases, such as en passant. `add_if_legal_simple` should be used then
-fn move_is_not_check(
-    board: &ChessBoard,
-    mv: ChessMove,
-    king_pos: Square,
-    is_in_check: bool,
-) -> bool {
-    !(is_in_check || is_pinned_to_piece(board, mv.from, king_pos))
-        || move_is_not_check_simple(board, mv)
+/// Returns the castling move to the given side, if the side to move has the castling right
+/// and no pieces stand in the way. Whether the king castles through check is not checked
+pub fn unobstructed_castling(board: &ChessBoard, kingside: bool) -> Option<(ChessMove, Castling)> {
+    let color = board.to_move;
+    let can_castle = if kingside {
+        board.can_castle_kingside(color)
+    } else {
+        board.can_castle_queenside(color)
+    };
+    if !can_castle {
+        return None;
+    }
+    let mv = board.castling_move(color, kingside);
+    let castling = board
+        .castling_squares(mv)
+        .filter(|castling| board[castling.rook_from] == Piece::from_type_color(Rook, color))?;
+    // Every square the king and rook pass over or land on must be empty, except for the two castling pieces
+    let castling_pieces = BitBoard::from_square(castling.king_from).set(castling.rook_from);
+    let rook_path = attacks::between(castling.rook_from, castling.rook_to).set(castling.rook_to);
+    if ((castling_king_path(castling) | rook_path) & board.occupied() & !castling_pieces).is_empty()
+    {
+        Some((mv, castling))
+    } else {
+        None
+    }
 }
 
-/// Checks that the move does not put the player in check
-/// This check is more expensive, but works for _all_ positions
-fn move_is_not_check_simple(board: &ChessBoard, mv: ChessMove) -> bool {
-    let mut cloned = board.clone();
-    cloned.do_move(mv);
-
-    cloned.to_move = !cloned.to_move;
-
-    !is_attacked(&cloned, king_pos(&cloned))
+/// Returns the squares the king stands on or passes over when castling, which may not be attacked
+pub fn castling_king_path(castling: Castling) -> BitBoard {
+    attacks::between(castling.king_from, castling.king_to)
+        .set(castling.king_from)
+        .set(castling.king_to)
 }
 
-/// Checks that the move does not put the player in check
-/// , and add the move to the vector
-/// Does not work in some special cases, such as en passant. `add_if_legal_simple` should be used then
 #[inline(never)]
-fn add_if_legal(
+fn legal_moves_for_pawn(
     board: &ChessBoard,
-    mv: ChessMove,
-    moves: &mut Vec<ChessMove>,
-    king_pos: Square,
-    is_in_check: bool,
+    square: Square,
+    stage: Stage,
+    check_info: &CheckInfo,
+    moves: &mut StagedMoves,
 ) {
-    if move_is_not_check(board, mv, king_pos, is_in_check) {
-        moves.push(mv);
-    }
-}
+    let color = board.to_move;
+    let rank = square.rank();
+    let legal_targets = check_info.legal_targets(square);
 
-/// Checks that the move does not put the player in check
-/// , and add the move to the vector
-/// This check is more expensive, but works for _all_ positions
-#[inline(never)]
-fn add_if_legal_simple(board: &ChessBoard, mv: ChessMove, moves: &mut Vec<ChessMove>) {
-    if move_is_not_check_simple(board, mv) {
-        moves.push(mv);
-    }
-}
+    let (start_rank, prom_rank) = if color == White { (6, 1) } else { (1, 6) };
+    debug_assert!(rank > 0 && rank < 7);
 
-#[inline(never)]
-pub fn is_pinned_to_piece(board: &ChessBoard, pinee_pos: Square, pinner_pos: Square) -> bool {
-    debug_assert!(pinee_pos != pinner_pos);
-    let (pinner_file, pinner_rank) = pinner_pos.file_rank(); // The piece it is pinned to
-    let (pinee_file, pinee_rank) = pinee_pos.file_rank(); // The piece that is pinned
-    let (file_diff, rank_diff) = (
-        (pinee_file as i8 - pinner_file as i8),
-        (pinee_rank as i8 - pinner_rank as i8),
-    );
-    // If on the same diagonal
-    if file_diff.abs() == rank_diff.abs() {
-        let (dir_file, dir_rank) = (file_diff.signum(), rank_diff.signum());
-        let (mut cur_file, mut cur_rank) = (pinner_file as i8, pinner_rank as i8);
-
-        // Check for enemy pieces, or pieces blocking, on the diagonal
-        loop {
-            cur_file += dir_file;
-            cur_rank += dir_rank;
-            if cur_file < 0 || cur_file >= 8 || cur_rank < 0 || cur_rank >= 8 {
-                return false;
-            }
-            if cur_file == pinee_file as i8 {
-                continue;
+    if stage.has_captures() {
+        // Checks if there are any pieces available for capture,
+        // including en passant capture
+        let capture_squares = attacks::pawn_attacks(color, square);
+        for take_square in capture_squares & board.color_bitboard(!color) & legal_targets {
+            if rank == prom_rank {
+                for piece_type in &[Queen, Rook, Bishop, Knight] {
+                    moves
+                        .winning
+                        .push(ChessMove::new_prom(square, take_square, *piece_type));
+                }
+            } else {
+                moves.push_capture(board, ChessMove::new(square, take_square));
             }
-            let piece = board[Square::from_ints(cur_file as u8, cur_rank as u8)];
-            match piece.piece_type() {
-                Empty => continue,
-                // If you first find an opposite color queen/bishop, you're pinned
-                Bishop | Queen if piece.color().unwrap() != board.to_move => return true,
-                // If you first find any other piece blocking the check, you're not pinned
-                _ => return false,
+        }
+        if let Some(ep_square) = board.en_passant_square() {
+            if capture_squares.get(ep_square) && en_passant_is_legal(board, square, ep_square) {
+                moves.push_capture(board, ChessMove::new(square, ep_square));
             }
         }
     }
-    // If on the same file
-    else if file_diff == 0 {
-        debug_assert!(rank_diff != 0);
-        let dir_rank = rank_diff.signum();
-        let mut cur_rank = pinner_rank as i8;
-
-        // Check for enemy pieces, or pieces blocking, on the file
-        loop {
-            cur_rank += dir_rank;
-            if cur_rank < 0 || cur_rank >= 8 {
-                return false;
-            }
-            if cur_rank == pinee_rank as i8 {
-                continue;
-            }
-            let piece = board[Square::from_ints(pinner_file as u8, cur_rank as u8)];
-            match piece.piece_type() {
-                Empty => continue,
-                Rook | Queen if piece.color().unwrap() != board.to_move => return true,
-                _ => return false,
+
+    //Checks if the pawn can walk forward one or two squares, promote
+    let square_in_front = pawn_push(color, square);
+
+    if board.piece_at(square_in_front).is_empty() {
+        if legal_targets.get(square_in_front) {
+            if rank == prom_rank {
+                if stage.has_captures() {
+                    for piece_type in &[Queen, Rook, Bishop, Knight] {
+                        moves.winning.push(ChessMove::new_prom(
+                            square,
+                            square_in_front,
+                            *piece_type,
+                        ));
+                    }
+                }
+            } else if stage.has_quiets() {
+                moves.quiet.push(ChessMove::new(square, square_in_front));
             }
         }
-    } else if rank_diff == 0 {
-        debug_assert!(file_diff != 0);
-        let dir_file = file_diff.signum();
-        let mut cur_file = pinner_file as i8;
-
-        // Check for enemy pieces, or pieces blocking, on the file
-        loop {
-            cur_file += dir_file;
-            if cur_file < 0 || cur_file >= 8 {
-                return false;
-            }
-            if cur_file == pinee_file as i8 {
-                continue;
-            }
-            let piece = board[Square::from_ints(cur_file as u8, pinner_rank as u8)];
-            if piece.is_empty() {
-                continue;
-            }
-            if (piece.piece_type() == Rook || piece.piece_type() == Queen)
-                && piece.color().unwrap() != board.to_move
+        if rank == start_rank && stage.has_quiets() {
+            let square_2_in_front = pawn_push(color, square_in_front);
+            if board.piece_at(square_2_in_front).is_empty() && legal_targets.get(square_2_in_front)
             {
-                return true;
+                moves.quiet.push(ChessMove::new(square, square_2_in_front));
             }
-            return false;
         }
-    } else {
-        false
     }
 }
 
-/// Returns whether a square is under attack
-pub fn is_attacked_by_color(board: &ChessBoard, square: Square, color: Color) -> bool {
-    // Direction enemy pawns are coming from
-    let pawn_direction: i8 = if color == White { -1 } else { 1 };
-    let file = square.file() as i8;
-    let rank = square.rank() as i8;
-
-    if file > 0 && ((color == White && rank > 1) || (color == Black && rank < 6)) {
-        let pawn_square = Square::from_ints(file as u8 - 1, (rank as i8 + pawn_direction) as u8);
+/// The squares from which the side to move would give check, computed once per position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CheckSquares {
+    pub enemy_king: Square,
+    /// For each piece type, the squares it would give check from. Indexed by `PieceType`
+    pub squares: [BitBoard; 7],
+    /// The side to move's pieces that block one of its own sliders from the enemy king.
+    /// Moving one off the line gives a discovered check
+    pub discoverers: BitBoard,
+}
 
-        if board[pawn_square].piece_type() == Pawn && board[pawn_square].color().unwrap() != color {
-            return true;
+impl CheckSquares {
+    pub fn new(board: &ChessBoard) -> Self {
+        let color = board.to_move;
+        let enemy_king = board.king_pos(!color);
+        let occupied = board.occupied();
+        let bishop_squares = attacks::bishop_attacks(enemy_king, occupied);
+        let rook_squares = attacks::rook_attacks(enemy_king, occupied);
+        let squares = [
+            BitBoard::empty(),
+            attacks::pawn_attacks(!color, enemy_king),
+            attacks::knight_attacks(enemy_king),
+            bishop_squares,
+            rook_squares,
+            bishop_squares | rook_squares,
+            BitBoard::empty(),
+        ];
+
+        // Own sliders that would attack the king if not for the pieces in between
+        let own_pieces = board.color_bitboard(color);
+        let enemy_pieces = board.color_bitboard(!color);
+        let snipers = ((attacks::rook_attacks(enemy_king, enemy_pieces)
+            & (board.piece_type_bitboard(Rook) | board.piece_type_bitboard(Queen)))
+            | (attacks::bishop_attacks(enemy_king, enemy_pieces)
+                & (board.piece_type_bitboard(Bishop) | board.piece_type_bitboard(Queen))))
+            & own_pieces;
+        let mut discoverers = BitBoard::empty();
+        for sniper in snipers {
+            let blockers = attacks::between(enemy_king, sniper) & occupied;
+            if !blockers.has_several() {
+                discoverers |= blockers & own_pieces;
+            }
         }
-    }
 
-    if file < 7 && ((color == White && rank > 1) || (color == Black && rank < 6)) {
-        let pawn_square = Square::from_ints(file as u8 + 1, (rank as i8 + pawn_direction) as u8);
-
-        if board[pawn_square].piece_type() == Pawn && board[pawn_square].color().unwrap() != color {
-            return true;
+        CheckSquares {
+            enemy_king,
+            squares,
+            discoverers,
         }
     }
 
-    for &(i, j) in &[(0, 1), (1, 0), (0, -1), (-1, 0)] {
-        if check_threats_in_direction(i, j, board, square, &[Queen, Rook], color) {
-            return true;
-        }
-    }
-    for &(i, j) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
-        if check_threats_in_direction(i, j, board, square, &[Queen, Bishop], color) {
+    /// Returns whether a legal move gives check, directly or by discovery, without playing it
+    pub fn gives_check(&self, board: &ChessBoard, mv: ChessMove) -> bool {
+        let moving_type = board[mv.from].piece_type();
+
+        if let Some(castling) = board.castling_squares(mv) {
+            // The rook may give check, or the king may uncover a check
+            let color = board.to_move;
+            let occupied = board
+                .occupied()
+                .clear(castling.king_from)
+                .clear(castling.rook_from)
+                .set(castling.king_to)
+                .set(castling.rook_to);
+            let diagonal_sliders =
+                board.piece_bitboard(Bishop, color) | board.piece_bitboard(Queen, color);
+            let straight_sliders = (board.piece_bitboard(Rook, color)
+                | board.piece_bitboard(Queen, color))
+            .clear(castling.rook_from)
+            .set(castling.rook_to);
+            return !((attacks::bishop_attacks(self.enemy_king, occupied) & diagonal_sliders)
+                | (attacks::rook_attacks(self.enemy_king, occupied) & straight_sliders))
+                .is_empty();
+        }
+
+        if self.discoverers.get(mv.from) && !attacks::line(self.enemy_king, mv.from).get(mv.to) {
             return true;
         }
-    }
-
-    for &(i, j) in &[
-        (-2, -1),
-        (-2, 1),
-        (-1, -2),
-        (-1, 2),
-        (1, -2),
-        (1, 2),
-        (2, -1),
-        (2, 1),
-    ] {
-        if file + i < 0 || file + i >= 8 || rank + j < 0 || rank + j >= 8 {
-            continue;
-        }
-        let new_pos = Square(((rank + j) * 8 + file + i) as u8);
 
-        if board[new_pos].piece_type() == Knight && board[new_pos].color().unwrap() != color {
-            return true;
-        }
-    }
-    for i in -1..2 {
-        for j in -1..2 {
-            if file + i < 0 || file + i >= 8 || rank + j < 0 || rank + j >= 8 || (j == 0 && i == 0)
-            {
-                continue;
+        match mv.prom {
+            // The pawn may have stood in the way of its own promoted piece
+            Some(prom) => {
+                let occupied = board.occupied().clear(mv.from);
+                let attacks = match prom {
+                    Knight => attacks::knight_attacks(mv.to),
+                    Bishop => attacks::bishop_attacks(mv.to, occupied),
+                    Rook => attacks::rook_attacks(mv.to, occupied),
+                    _ => attacks::queen_attacks(mv.to, occupied),
+                };
+                attacks.get(self.enemy_king)
             }
-            let new_pos = Square(((rank + j) * 8 + file + i) as u8);
-
-            // Check that there is no enemy king around
-            if board[new_pos].piece_type() == King && board[new_pos].color().unwrap() != color {
-                return true;
+            None if self.squares[moving_type as usize].get(mv.to) => true,
+            None if moving_type == Pawn && Some(mv.to) == board.en_passant_square() => {
+                // Removing the captured pawn may open a line to the king
+                let color = board.to_move;
+                let occupied = board
+                    .occupied()
+                    .clear(mv.from)
+                    .clear(Square::from_ints(mv.to.file(), mv.from.rank()))
+                    .set(mv.to);
+                let diagonal_sliders =
+                    board.piece_bitboard(Bishop, color) | board.piece_bitboard(Queen, color);
+                let straight_sliders =
+                    board.piece_bitboard(Rook, color) | board.piece_bitboard(Queen, color);
+                !((attacks::bishop_attacks(self.enemy_king, occupied) & diagonal_sliders)
+                    | (attacks::rook_attacks(self.enemy_king, occupied) & straight_sliders))
+                    .is_empty()
             }
+            None => false,
         }
     }
-    false
 }
 
-/// Returns whether a square is under attack by the side not to move
-#[inline(never)]
-pub fn is_attacked(board: &ChessBoard, square: Square) -> bool {
-    is_attacked_by_color(board, square, board.side_to_move())
+/// Returns whether a legal move gives check, directly or by discovery, without playing it.
+/// When testing many moves in the same position, use `CheckSquares` directly instead.
+pub fn gives_check(board: &ChessBoard, mv: ChessMove) -> bool {
+    CheckSquares::new(board).gives_check(board, mv)
 }
 
-/// Checks if there are any threats in the direction to the given color.
-fn check_threats_in_direction(
-    i: i8,
-    j: i8,
-    board: &ChessBoard,
-    square: Square,
-    threats: &[PieceType],
-    color: Color,
-) -> bool {
-    let mut file = (square.0 & 0b0000_0111) as i8;
-    let mut rank = (square.0 >> 3) as i8;
+/// Piece values for static exchange evaluation, in centipawns, indexed by `PieceType`
+const SEE_VALUES: [i32; 7] = [0, 100, 300, 300, 500, 900, 20_000];
+
+fn see_value(piece_type: PieceType) -> i32 {
+    SEE_VALUES[piece_type as usize]
+}
+
+/// Static exchange evaluation of a legal move, in centipawns.
+///
+/// Plays out every capture on the target square, with both sides always recapturing
+/// with their least valuable piece, and either side free to stop capturing when it is ahead.
+/// Sliders behind the capturing pieces join in as the squares in front of them are cleared.
+/// Pins are not considered. Quiet moves are evaluated as the opponent's best capture of the moved piece.
+pub fn see(board: &ChessBoard, mv: ChessMove) -> i32 {
+    let moving_type = board[mv.from].piece_type();
+    if board.is_castling(mv) {
+        return 0;
+    }
+    let mut occupied = board.occupied().clear(mv.from);
+    let mut gain = [0; 32];
+
+    gain[0] = see_value(board[mv.to].piece_type());
+    if moving_type == Pawn && Some(mv.to) == board.en_passant_square() {
+        gain[0] = see_value(Pawn);
+        occupied = occupied.clear(Square::from_ints(mv.to.file(), mv.from.rank()));
+    }
+    let mut piece_on_square = moving_type;
+    if let Some(prom) = mv.prom {
+        gain[0] += see_value(prom) - see_value(Pawn);
+        piece_on_square = prom;
+    }
+
+    let diagonal_sliders = board.piece_type_bitboard(Bishop) | board.piece_type_bitboard(Queen);
+    let straight_sliders = board.piece_type_bitboard(Rook) | board.piece_type_bitboard(Queen);
+    let mut all_attackers = (attackers(board, mv.to, White, occupied)
+        | attackers(board, mv.to, Black, occupied))
+        & occupied;
+    let mut color = !board.to_move;
+    let mut depth = 0;
+
     loop {
-        file += i;
-        rank += j;
-        if file < 0 || rank < 0 || file >= 8 || rank >= 8 {
-            return false;
-        }
-        let piece = board[Square::from_ints(file as u8, rank as u8)];
-        match piece.piece_type() {
-            Empty => continue,
-            piece_type => {
-                if piece.color() == Some(color) {
-                    return false;
+        let own_attackers = all_attackers & board.color_bitboard(color);
+        let attacker =
+            match [Pawn, Knight, Bishop, Rook, Queen, King]
+                .iter()
+                .find_map(|&piece_type| {
+                    (own_attackers & board.piece_type_bitboard(piece_type))
+                        .first_square()
+                        .map(|square| (piece_type, square))
+                }) {
+                None => break,
+                // The king may only capture if the square is no longer defended
+                Some((King, _)) if !(all_attackers & board.color_bitboard(!color)).is_empty() => {
+                    break
                 }
+                Some(attacker) => attacker,
+            };
+        let (attacker_type, attacker_square) = attacker;
+
+        depth += 1;
+        gain[depth] = see_value(piece_on_square) - gain[depth - 1];
+        piece_on_square = attacker_type;
+        if attacker_type == Pawn && (mv.to.rank() == 0 || mv.to.rank() == 7) {
+            gain[depth] += see_value(Queen) - see_value(Pawn);
+            piece_on_square = Queen;
+        }
+
+        occupied = occupied.clear(attacker_square);
+        all_attackers = (all_attackers
+            | (attacks::bishop_attacks(mv.to, occupied) & diagonal_sliders)
+            | (attacks::rook_attacks(mv.to, occupied) & straight_sliders))
+            & occupied;
+        color = !color;
+    }
 
-                for threat in threats {
-                    if piece_type == *threat {
-                        return true;
-                    }
-                }
-                return false;
-            }
-        }
+    // Each side only continues the exchange if it gains from doing so
+    while depth > 0 {
+        gain[depth - 1] = -i32::max(-gain[depth - 1], gain[depth]);
+        depth -= 1;
     }
+    gain[0]
 }
 
-fn add_moves_diagonally(
-    board: &ChessBoard,
-    square: Square,
-    winning_moves: &mut Vec<ChessMove>,
-    active_moves: &mut Vec<ChessMove>,
-    quiet_moves: &mut Vec<ChessMove>,
-    king_pos: Square,
-    is_in_check: bool,
-) {
-    for &(i, j) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
-        add_moves_in_direction(
-            i,
-            j,
-            board,
-            square,
-            winning_moves,
-            active_moves,
-            quiet_moves,
-            king_pos,
-            is_in_check,
-        );
-    }
+/// Returns whether the static exchange evaluation of a legal move is at least `threshold`
+pub fn see_ge(board: &ChessBoard, mv: ChessMove, threshold: i32) -> bool {
+    see(board, mv) >= threshold
 }
 
-fn add_straight_moves(
-    board: &ChessBoard,
-    square: Square,
-    winning_moves: &mut Vec<ChessMove>,
-    active_moves: &mut Vec<ChessMove>,
-    quiet_moves: &mut Vec<ChessMove>,
-    king_pos: Square,
-    is_in_check: bool,
-) {
-    for &(i, j) in &[(0, 1), (1, 0), (0, -1), (-1, 0)] {
-        add_moves_in_direction(
-            i,
-            j,
-            board,
-            square,
-            winning_moves,
-            active_moves,
-            quiet_moves,
-            king_pos,
-            is_in_check,
-        );
+/// Checks whether an en passant capture leaves the king safe.
+/// Both pawns leave their squares, which may expose the king along a rank or diagonal,
+/// so the capture is checked exactly instead of with the pin rays.
+fn en_passant_is_legal(board: &ChessBoard, from: Square, ep_square: Square) -> bool {
+    let color = board.to_move;
+    let captured_square = Square::from_ints(ep_square.file(), from.rank());
+    let occupied = board
+        .occupied()
+        .clear(from)
+        .clear(captured_square)
+        .set(ep_square);
+    (attackers(board, king_pos(board), color, occupied) & !BitBoard::from_square(captured_square))
+        .is_empty()
+}
+
+/// Returns the square directly in front of a pawn of the given color
+fn pawn_push(color: Color, square: Square) -> Square {
+    match color {
+        White => Square(square.0 - 8),
+        Black => Square(square.0 + 8),
     }
 }
 
-fn add_moves_in_direction(
-    i: i8,
-    j: i8,
-    board: &ChessBoard,
-    square: Square,
-    winning_moves: &mut Vec<ChessMove>,
-    active_moves: &mut Vec<ChessMove>,
-    quiet_moves: &mut Vec<ChessMove>,
-    king_pos: Square,
-    is_in_check: bool,
-) {
-    // let mut pos = square.0 as i8;
-    let mut file = (square.0 & 0b0000_0111) as i8;
-    let mut rank = (square.0 >> 3) as i8;
-    loop {
-        file += i;
-        rank += j;
+/// Returns whether the piece on `pinee_pos` is pinned by an enemy slider to the piece on `pinner_pos`
+#[inline(never)]
+pub fn is_pinned_to_piece(board: &ChessBoard, pinee_pos: Square, pinner_pos: Square) -> bool {
+    debug_assert!(pinee_pos != pinner_pos);
+    let enemy_pieces = board.color_bitboard(!board.to_move);
+    let occupied = board.occupied().clear(pinee_pos);
+    let snipers = ((attacks::rook_attacks(pinner_pos, occupied)
+        & (board.piece_type_bitboard(Rook) | board.piece_type_bitboard(Queen)))
+        | (attacks::bishop_attacks(pinner_pos, occupied)
+            & (board.piece_type_bitboard(Bishop) | board.piece_type_bitboard(Queen))))
+        & enemy_pieces;
+    snipers
+        .squares()
+        .any(|sniper| attacks::between(pinner_pos, sniper).get(pinee_pos))
+}
 
-        if file < 0 || rank < 0 || file >= 8 || rank >= 8 {
-            break;
-        }
-        let target_square = Square::from_ints(file as u8, rank as u8);
-        let piece_to = board.piece_at(target_square);
+/// Returns every piece of the opposite color of `color` that attacks the square,
+/// with the given squares treated as occupied
+fn attackers(board: &ChessBoard, square: Square, color: Color, occupied: BitBoard) -> BitBoard {
+    let diagonal_sliders = board.piece_type_bitboard(Bishop) | board.piece_type_bitboard(Queen);
+    let straight_sliders = board.piece_type_bitboard(Rook) | board.piece_type_bitboard(Queen);
+
+    ((attacks::pawn_attacks(color, square) & board.piece_type_bitboard(Pawn))
+        | (attacks::knight_attacks(square) & board.piece_type_bitboard(Knight))
+        | (attacks::king_attacks(square) & board.piece_type_bitboard(King))
+        | (attacks::bishop_attacks(square, occupied) & diagonal_sliders)
+        | (attacks::rook_attacks(square, occupied) & straight_sliders))
+        & board.color_bitboard(!color)
+}
 
-        let mv = ChessMove::new(square, target_square);
-        if piece_to.is_empty() {
-            add_if_legal(board, mv, quiet_moves, king_pos, is_in_check);
-        } else {
-            if piece_to.color().unwrap() != board.to_move {
-                match piece_to
-                    .value()
-                    .abs()
-                    .partial_cmp(&board[square].value().abs())
-                    .unwrap()
-                {
-                    Ordering::Less => add_if_legal(board, mv, quiet_moves, king_pos, is_in_check),
-                    Ordering::Equal => add_if_legal(board, mv, active_moves, king_pos, is_in_check),
-                    Ordering::Greater => {
-                        add_if_legal(board, mv, winning_moves, king_pos, is_in_check)
-                    }
-                }
-            }
-            break; // Break after finding a piece, friend or foe
-        }
+/// Returns the squares of every piece of `color` that attacks the square
+pub fn attackers_to(board: &ChessBoard, square: Square, color: Color) -> BitBoard {
+    attackers(board, square, !color, board.occupied())
+}
+
+/// Returns the squares of every piece giving check to the side to move.
+/// Has two squares in double check
+pub fn checkers(board: &ChessBoard) -> BitBoard {
+    attackers_to(board, king_pos(board), !board.side_to_move())
+}
+
+/// Returns every square attacked by a piece of `color`, including squares with friendly pieces
+pub fn attacked_squares(board: &ChessBoard, color: Color) -> BitBoard {
+    let occupied = board.occupied();
+    let mut attacked = BitBoard::empty();
+    for square in board.color_bitboard(color) {
+        attacked |= match board[square].piece_type() {
+            Pawn => attacks::pawn_attacks(color, square),
+            Knight => attacks::knight_attacks(square),
+            Bishop => attacks::bishop_attacks(square, occupied),
+            Rook => attacks::rook_attacks(square, occupied),
+            Queen => attacks::queen_attacks(square, occupied),
+            King => attacks::king_attacks(square),
+            Empty => BitBoard::empty(),
+        };
     }
+    attacked
+}
+
+/// Returns whether a square is under attack
+pub fn is_attacked_by_color(board: &ChessBoard, square: Square, color: Color) -> bool {
+    !attackers(board, square, color, board.occupied()).is_empty()
+}
+
+/// Returns whether a square is under attack by the side not to move
+#[inline(never)]
+pub fn is_attacked(board: &ChessBoard, square: Square) -> bool {
+    is_attacked_by_color(board, square, board.side_to_move())
 }
 
 pub fn king_pos(board: &ChessBoard) -> Square {
@@ -0,0 +1,100 @@
+use crate::chess_move::ChessMove;
+use crate::types::Square;
+use std::fmt;
+use std::ops;
+
+/// The maximum number of moves a `MoveList` can hold.
+/// No legal chess position has more than 218 moves.
+pub const MAX_MOVES: usize = 256;
+
+/// A fixed-capacity list of moves, stored inline.
+/// Lets the move generator run without heap allocations.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [ChessMove; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> Self {
+        MoveList {
+            moves: [ChessMove::new(Square(0), Square(0)); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Adds a move to the end of the list.
+    /// Panics if the list is already full
+    #[inline]
+    pub fn push(&mut self, mv: ChessMove) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<ChessMove> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            Some(self.moves[self.len])
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, moves: &[ChessMove]) {
+        self.moves[self.len..self.len + moves.len()].copy_from_slice(moves);
+        self.len += moves.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn as_slice(&self) -> &[ChessMove] {
+        &self.moves[..self.len]
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [ChessMove] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ops::Deref for MoveList {
+    type Target = [ChessMove];
+    fn deref(&self) -> &[ChessMove] {
+        self.as_slice()
+    }
+}
+
+impl ops::DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [ChessMove] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a ChessMove;
+    type IntoIter = std::slice::Iter<'a, ChessMove>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl PartialEq for MoveList {
+    fn eq(&self, other: &MoveList) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for MoveList {}
+
+impl fmt::Debug for MoveList {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Debug::fmt(self.as_slice(), fmt)
+    }
+}
@@ -0,0 +1,628 @@
+//! Reading games in [Portable Game Notation][1].
+//!
+//! `PgnReader` reads one game at a time from any buffered source, so a database of any size
+//! can be processed without loading it into memory. Every move is checked against the position,
+//! and errors report the line and column where they were found.
+//!
+//! [1]: https://www.thechessdrum.net/PGN_Reference.txt
+
+use crate::chess_board::ChessBoard;
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::path::Path;
+
+/// The tags every game must have, in the order they are exported
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// A game read from PGN
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnGame {
+    /// The tag pairs, in the order they were read
+    pub tags: Vec<(String, String)>,
+    /// The position the game starts from, which is set by the `FEN` tag
+    pub start_board: ChessBoard,
+    pub mainline: Variation,
+    /// The result from the game termination marker, or `None` for `*`.
+    /// Without a marker, the result is taken from the `Result` tag
+    pub result: Option<GameResult>,
+}
+
+impl PgnGame {
+    /// Creates a game without tags or moves, starting from the position
+    pub fn new(start_board: ChessBoard) -> Self {
+        PgnGame {
+            tags: vec![],
+            start_board,
+            mainline: Variation::default(),
+            result: None,
+        }
+    }
+
+    /// Sets the value of a tag, replacing any earlier value
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag_name, _)| tag_name == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Returns the value of the first tag with the name
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the position at the end of the main line
+    pub fn final_board(&self) -> ChessBoard {
+        let mut board = self.start_board.clone();
+        for pgn_move in self.mainline.moves.iter() {
+            board.do_move(pgn_move.mv);
+        }
+        board
+    }
+}
+
+/// A line of moves, either the main line or an alternative to one of its moves
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Variation {
+    /// Comments before the first move
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>,
+}
+
+/// A move in a game, with its annotations
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mv: ChessMove,
+    /// Numeric annotation glyphs, like `$1` for a good move. Suffixes like `!?` are stored as their glyphs
+    pub nags: Vec<u8>,
+    /// Comments after the move
+    pub comments: Vec<String>,
+    /// Alternatives to this move, played from the position before it
+    pub variations: Vec<Variation>,
+}
+
+impl PgnMove {
+    pub fn new(mv: ChessMove) -> Self {
+        PgnMove {
+            mv,
+            nags: vec![],
+            comments: vec![],
+            variations: vec![],
+        }
+    }
+}
+
+/// Parses a game termination marker. The outer `None` means the input is not a marker
+fn parse_result(input: &str) -> Option<Option<GameResult>> {
+    match input {
+        "1-0" => Some(Some(GameResult::WhiteWin)),
+        "0-1" => Some(Some(GameResult::BlackWin)),
+        "1/2-1/2" => Some(Some(GameResult::Draw)),
+        "*" => Some(None),
+        _ => None,
+    }
+}
+
+/// Returns the numeric annotation glyph of a move suffix like `!?`
+fn suffix_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+/// Returns whether the variant named in a `Variant` tag is Chess960
+fn is_chess960_variant(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("960") || name.contains("fischer")
+}
+
+/// A line and column in the input, both counted from 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn error(self, kind: pgn::ErrorKind, message: String) -> pgn::Error {
+        pgn::Error::new(
+            kind,
+            format!("{} at line {}, column {}", message, self.line, self.column),
+        )
+    }
+
+    fn error_caused_by(
+        self,
+        kind: pgn::ErrorKind,
+        message: String,
+        source: pgn::Error,
+    ) -> pgn::Error {
+        pgn::Error::new_caused_by(
+            kind,
+            format!("{} at line {}, column {}", message, self.line, self.column),
+            source,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    TagStart,
+    TagEnd,
+    String(String),
+    /// A move, move number or game termination marker
+    Symbol(String),
+    Period,
+    Asterisk,
+    Nag(u8),
+    Comment(String),
+    VariationStart,
+    VariationEnd,
+}
+
+/// Splits the input into tokens, reading a line at a time
+struct Lexer<R> {
+    reader: R,
+    line: String,
+    // Byte index of the next character in `line`
+    index: usize,
+    position: Position,
+}
+
+impl<R: BufRead> Lexer<R> {
+    fn new(reader: R) -> Self {
+        Lexer {
+            reader,
+            line: String::new(),
+            index: 0,
+            position: Position { line: 0, column: 1 },
+        }
+    }
+
+    /// Reads the next line into `line`. Returns false at the end of the input
+    fn read_line(&mut self) -> Result<bool, pgn::Error> {
+        let mut bytes = vec![];
+        let num_read = self.reader.read_until(b'\n', &mut bytes).map_err(|err| {
+            pgn::Error::new_caused_by(
+                pgn::ErrorKind::IOError,
+                format!("Couldn't read line {}", self.position.line + 1),
+                err,
+            )
+        })?;
+        // Older databases are often in Latin-1, which can still be read apart from names and comments
+        self.line = String::from_utf8_lossy(&bytes).into_owned();
+        self.index = 0;
+        self.position = Position {
+            line: self.position.line + 1,
+            column: 1,
+        };
+        Ok(num_read > 0)
+    }
+
+    fn peek_char(&mut self) -> Result<Option<char>, pgn::Error> {
+        while self.index >= self.line.len() {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            // Lines starting with `%` are escaped, and ignored
+            if self.line.starts_with('%') {
+                self.index = self.line.len();
+            }
+        }
+        Ok(self.line[self.index..].chars().next())
+    }
+
+    fn next_char(&mut self) -> Result<Option<char>, pgn::Error> {
+        let ch = self.peek_char()?;
+        if let Some(ch) = ch {
+            self.index += ch.len_utf8();
+            self.position.column += 1;
+        }
+        Ok(ch)
+    }
+
+    /// Reads characters for as long as they match the predicate
+    fn take_while<F: Fn(char) -> bool>(&mut self, predicate: F) -> Result<String, pgn::Error> {
+        let mut output = String::new();
+        while let Some(ch) = self.peek_char()? {
+            if !predicate(ch) {
+                break;
+            }
+            output.push(ch);
+            self.next_char()?;
+        }
+        Ok(output)
+    }
+
+    /// Returns the next token and where it starts, or `None` at the end of the input
+    fn next_token(&mut self) -> Result<Option<(Token, Position)>, pgn::Error> {
+        while let Some(ch) = self.peek_char()? {
+            // The byte order mark is skipped along with whitespace
+            if !ch.is_whitespace() && ch != '\u{feff}' {
+                break;
+            }
+            self.next_char()?;
+        }
+        let position = self.position;
+        let ch = match self.next_char()? {
+            Some(ch) => ch,
+            None => return Ok(None),
+        };
+        let token = match ch {
+            '[' => Token::TagStart,
+            ']' => Token::TagEnd,
+            '.' => Token::Period,
+            '*' => Token::Asterisk,
+            '(' => Token::VariationStart,
+            ')' => Token::VariationEnd,
+            '"' => Token::String(self.read_string(position)?),
+            '{' => Token::Comment(self.read_comment(position)?),
+            ';' => {
+                let comment = self.take_while(|ch| ch != '\n')?;
+                Token::Comment(comment.trim().to_string())
+            }
+            '$' => {
+                let digits = self.take_while(|ch| ch.is_ascii_digit())?;
+                let nag = digits.parse().map_err(|_| {
+                    position.error(
+                        pgn::ErrorKind::ParseError,
+                        format!("Invalid annotation glyph ${}", digits),
+                    )
+                })?;
+                Token::Nag(nag)
+            }
+            '!' | '?' => {
+                let suffix = format!("{}{}", ch, self.take_while(|ch| ch == '!' || ch == '?')?);
+                let nag = suffix_nag(&suffix).ok_or_else(|| {
+                    position.error(
+                        pgn::ErrorKind::ParseError,
+                        format!("Invalid move suffix {}", suffix),
+                    )
+                })?;
+                Token::Nag(nag)
+            }
+            _ if ch.is_ascii_alphanumeric() => {
+                let rest =
+                    self.take_while(|ch| ch.is_ascii_alphanumeric() || "_+#=:-/".contains(ch))?;
+                Token::Symbol(format!("{}{}", ch, rest))
+            }
+            _ => {
+                return Err(position.error(
+                    pgn::ErrorKind::ParseError,
+                    format!("Unexpected character '{}'", ch),
+                ))
+            }
+        };
+        Ok(Some((token, position)))
+    }
+
+    /// Reads a tag value after its opening quote, with `\"` and `\\` escaped
+    fn read_string(&mut self, start: Position) -> Result<String, pgn::Error> {
+        let mut output = String::new();
+        loop {
+            match self.next_char()? {
+                Some('"') => return Ok(output),
+                Some('\\') => match self.next_char()? {
+                    Some(ch @ '"') | Some(ch @ '\\') => output.push(ch),
+                    _ => {
+                        return Err(self.position.error(
+                            pgn::ErrorKind::ParseError,
+                            "Invalid escape in string".to_string(),
+                        ))
+                    }
+                },
+                Some('\n') | None => {
+                    return Err(start.error(
+                        pgn::ErrorKind::ParseError,
+                        "Unterminated string".to_string(),
+                    ))
+                }
+                Some(ch) => output.push(ch),
+            }
+        }
+    }
+
+    /// Reads a brace comment after its opening brace. Line breaks and runs of whitespace
+    /// become single spaces, so the comment reads the same however it was wrapped
+    fn read_comment(&mut self, start: Position) -> Result<String, pgn::Error> {
+        let mut output = String::new();
+        loop {
+            match self.next_char()? {
+                Some('}') => return Ok(output.split_whitespace().collect::<Vec<_>>().join(" ")),
+                Some(ch) => output.push(ch),
+                None => {
+                    return Err(start.error(
+                        pgn::ErrorKind::ParseError,
+                        "Unterminated comment".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Skips input until the first tag of the next game, which is a line starting with `[`
+    /// that does not directly follow another tag line
+    fn skip_to_next_game(&mut self) -> Result<(), pgn::Error> {
+        let mut after_tag = self.line.starts_with('[');
+        loop {
+            if !self.read_line()? {
+                return Ok(());
+            }
+            if self.line.starts_with('[') && !after_tag {
+                return Ok(());
+            }
+            after_tag = self.line.starts_with('[');
+        }
+    }
+}
+
+/// A line being read, with the position after its last move
+struct OpenLine {
+    variation: Variation,
+    board: ChessBoard,
+    last_reverse_move: Option<ChessReverseMove>,
+}
+
+/// Reads games from PGN, one at a time.
+///
+/// As an iterator, it yields every game in the input. After an error, reading continues from the next game.
+pub struct PgnReader<R> {
+    lexer: Lexer<R>,
+    peeked: Option<(Token, Position)>,
+}
+
+impl PgnReader<io::BufReader<fs::File>> {
+    /// Opens a `.pgn` file for reading
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, pgn::Error> {
+        let file = fs::File::open(path.as_ref()).map_err(|err| {
+            pgn::Error::new_caused_by(
+                pgn::ErrorKind::IOError,
+                format!("Couldn't open PGN file {}", path.as_ref().display()),
+                err,
+            )
+        })?;
+        Ok(Self::new(io::BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    /// Reads games from any buffered source, such as a file or `&[u8]`
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            lexer: Lexer::new(reader),
+            peeked: None,
+        }
+    }
+
+    /// Reads the next game, or returns `None` at the end of the input.
+    /// After an error, the rest of the game is skipped.
+    pub fn read_game(&mut self) -> Result<Option<PgnGame>, pgn::Error> {
+        let game = self.parse_game();
+        if game.is_err() {
+            self.peeked = None;
+            self.lexer.skip_to_next_game()?;
+        }
+        game
+    }
+
+    fn peek_token(&mut self) -> Result<Option<&(Token, Position)>, pgn::Error> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next_token(&mut self) -> Result<Option<(Token, Position)>, pgn::Error> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    /// Reads the next token, which must be the expected kind
+    fn expect_token<T, F>(&mut self, description: &str, matches: F) -> Result<T, pgn::Error>
+    where
+        F: FnOnce(Token) -> Option<T>,
+    {
+        match self.next_token()? {
+            Some((token, position)) => matches(token).ok_or_else(|| {
+                position.error(
+                    pgn::ErrorKind::ParseError,
+                    format!("Expected {}", description),
+                )
+            }),
+            None => Err(self.lexer.position.error(
+                pgn::ErrorKind::ParseError,
+                format!("Expected {}, found end of input", description),
+            )),
+        }
+    }
+
+    fn parse_game(&mut self) -> Result<Option<PgnGame>, pgn::Error> {
+        let mut tags = vec![];
+        let mut fen_position = None;
+        while let Some(&(Token::TagStart, position)) = self.peek_token()? {
+            self.next_token()?;
+            let name = self.expect_token("tag name", |token| match token {
+                Token::Symbol(name) => Some(name),
+                _ => None,
+            })?;
+            let value = self.expect_token("tag value", |token| match token {
+                Token::String(value) => Some(value),
+                _ => None,
+            })?;
+            self.expect_token("]", |token| match token {
+                Token::TagEnd => Some(()),
+                _ => None,
+            })?;
+            if name == "FEN" {
+                fen_position = Some(position);
+            }
+            tags.push((name, value));
+        }
+        if tags.is_empty() && self.peek_token()?.is_none() {
+            return Ok(None);
+        }
+
+        let mut game = PgnGame {
+            tags,
+            start_board: ChessBoard::start_board(),
+            mainline: Variation::default(),
+            result: None,
+        };
+        if let (Some(fen), Some(position)) = (game.tag("FEN"), fen_position) {
+            game.start_board = ChessBoard::from_fen(fen).map_err(|err| {
+                position.error_caused_by(
+                    pgn::ErrorKind::IllegalPosition,
+                    format!("Invalid FEN tag \"{}\"", fen),
+                    err,
+                )
+            })?;
+        }
+        if game.tag("Variant").is_some_and(is_chess960_variant) {
+            game.start_board.set_chess960(true);
+        }
+
+        let (mainline, result) = self.parse_movetext(&game.start_board)?;
+        game.mainline = mainline;
+        game.result = match result {
+            Some(result) => result,
+            None => game.tag("Result").and_then(parse_result).flatten(),
+        };
+        Ok(Some(game))
+    }
+
+    /// Reads the moves of a game, with their annotations and variations.
+    /// Returns the main line, and the result from the termination marker if there is one
+    fn parse_movetext(
+        &mut self,
+        start_board: &ChessBoard,
+    ) -> Result<(Variation, Option<Option<GameResult>>), pgn::Error> {
+        let mut lines = vec![OpenLine {
+            variation: Variation::default(),
+            board: start_board.clone(),
+            last_reverse_move: None,
+        }];
+        let mut result = None;
+
+        loop {
+            // Without a termination marker, the game ends at the tags of the next game
+            match self.peek_token()? {
+                None => break,
+                Some((Token::TagStart, _)) if lines.len() == 1 => break,
+                _ => (),
+            }
+            let (token, position) = self.next_token()?.unwrap();
+            let line = lines.last_mut().unwrap();
+            match token {
+                Token::Symbol(ref symbol) if symbol.chars().all(|ch| ch.is_ascii_digit()) => (),
+                Token::Period => (),
+                Token::Symbol(ref symbol) if parse_result(symbol).is_some() => {
+                    result = parse_result(symbol);
+                }
+                Token::Asterisk => result = Some(None),
+                Token::Symbol(san) => {
+                    let mv = line.board.move_from_san(&san).map_err(|err| {
+                        position.error_caused_by(
+                            pgn::ErrorKind::IllegalMove,
+                            format!("Illegal move {}", san),
+                            err,
+                        )
+                    })?;
+                    line.last_reverse_move = Some(line.board.do_move(mv));
+                    line.variation.moves.push(PgnMove::new(mv));
+                }
+                Token::Nag(nag) => match line.variation.moves.last_mut() {
+                    Some(pgn_move) => pgn_move.nags.push(nag),
+                    None => {
+                        return Err(position.error(
+                            pgn::ErrorKind::ParseError,
+                            "Annotation glyph before any move".to_string(),
+                        ))
+                    }
+                },
+                Token::Comment(comment) => match line.variation.moves.last_mut() {
+                    Some(pgn_move) => pgn_move.comments.push(comment),
+                    None => line.variation.comments.push(comment),
+                },
+                Token::VariationStart => {
+                    let reverse_move = line.last_reverse_move.ok_or_else(|| {
+                        position.error(
+                            pgn::ErrorKind::ParseError,
+                            "Variation before any move".to_string(),
+                        )
+                    })?;
+                    let mut board = line.board.clone();
+                    board.reverse_move(reverse_move);
+                    lines.push(OpenLine {
+                        variation: Variation::default(),
+                        board,
+                        last_reverse_move: None,
+                    });
+                }
+                Token::VariationEnd if lines.len() > 1 => {
+                    let variation = lines.pop().unwrap().variation;
+                    let parent = lines.last_mut().unwrap();
+                    parent
+                        .variation
+                        .moves
+                        .last_mut()
+                        .unwrap()
+                        .variations
+                        .push(variation);
+                }
+                Token::VariationEnd => {
+                    return Err(
+                        position.error(pgn::ErrorKind::ParseError, "Unmatched )".to_string())
+                    )
+                }
+                Token::TagStart | Token::TagEnd | Token::String(_) => {
+                    return Err(position.error(
+                        pgn::ErrorKind::ParseError,
+                        format!("Unexpected {:?} in movetext", token),
+                    ))
+                }
+            }
+            if result.is_some() {
+                if lines.len() > 1 {
+                    return Err(position.error(
+                        pgn::ErrorKind::ParseError,
+                        "Game result inside a variation".to_string(),
+                    ));
+                }
+                break;
+            }
+        }
+
+        if lines.len() > 1 {
+            return Err(self.lexer.position.error(
+                pgn::ErrorKind::ParseError,
+                "Unterminated variation".to_string(),
+            ));
+        }
+        Ok((lines.pop().unwrap().variation, result))
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, pgn::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_game().transpose()
+    }
+}
@@ -0,0 +1,270 @@
+//! Writing games in the export format of [Portable Game Notation][1].
+//!
+//! Games are written with the seven-tag roster first, and movetext wrapped to 80 columns.
+//! The output reads back through `PgnReader` to the same game.
+//!
+//! [1]: https://www.thechessdrum.net/PGN_Reference.txt
+
+use crate::chess_board::ChessBoard;
+use crate::chess_move::ChessMove;
+use crate::pgn_reader::{PgnGame, Variation, SEVEN_TAG_ROSTER};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+/// The longest line written in the movetext, unless a single token is longer
+pub const MAX_LINE_LENGTH: usize = 80;
+
+/// Returns the game termination marker for the result
+fn result_marker(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWin) => "1-0",
+        Some(GameResult::BlackWin) => "0-1",
+        Some(GameResult::Draw) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// Returns the value of a seven-tag roster tag that the game does not set
+fn default_tag_value(name: &str) -> &'static str {
+    match name {
+        "Date" => "????.??.??",
+        _ => "?",
+    }
+}
+
+/// Returns the number of the full move being played in `board`, in a game that started at `start_board`.
+/// Like in FEN, it starts at 1 and increases after Black's move.
+fn full_move_number(start_board: &ChessBoard, board: &ChessBoard) -> u16 {
+    // `move_num` counts plies from the full move number of the starting FEN
+    let plies = board.move_num - start_board.move_num;
+    let offset = if start_board.side_to_move() == Black {
+        1
+    } else {
+        0
+    };
+    start_board.move_num.max(1) + (plies + offset) / 2
+}
+
+/// The movetext as tokens separated by spaces, before it is wrapped into lines
+#[derive(Default)]
+struct Movetext {
+    // Each token, and whether it must end its line
+    tokens: Vec<(String, bool)>,
+    // Whether the next token starts a variation
+    open_variation: bool,
+}
+
+impl Movetext {
+    fn push(&mut self, token: String) {
+        if self.open_variation {
+            self.open_variation = false;
+            self.tokens.push((format!("({}", token), false));
+        } else {
+            self.tokens.push((token, false));
+        }
+    }
+
+    fn push_comment(&mut self, comment: &str) {
+        if comment.contains('}') {
+            // Only a rest of line comment can hold a closing brace
+            if self.open_variation {
+                self.open_variation = false;
+                self.tokens.push(("(".to_string(), false));
+            }
+            self.tokens.push((format!("; {}", comment), true));
+        } else if comment.is_empty() {
+            self.push("{}".to_string());
+        } else {
+            let words: Vec<&str> = comment.split_whitespace().collect();
+            let last = words.len() - 1;
+            for (i, word) in words.iter().enumerate() {
+                let mut token = word.to_string();
+                if i == 0 {
+                    token.insert(0, '{');
+                }
+                if i == last {
+                    token.push('}');
+                }
+                self.push(token);
+            }
+        }
+    }
+
+    fn start_variation(&mut self) {
+        self.open_variation = true;
+    }
+
+    fn end_variation(&mut self) {
+        match self.tokens.last_mut() {
+            _ if self.open_variation => {
+                self.open_variation = false;
+                self.tokens.push(("()".to_string(), false));
+            }
+            Some((token, false)) => token.push(')'),
+            _ => self.tokens.push((")".to_string(), false)),
+        }
+    }
+
+    /// Writes the tokens as lines of at most `MAX_LINE_LENGTH` characters
+    fn to_lines(&self) -> String {
+        let mut output = String::new();
+        let mut line_length = 0;
+        let mut must_break = false;
+        for (token, ends_line) in self.tokens.iter() {
+            let token_length = token.chars().count();
+            if line_length > 0 && (must_break || line_length + 1 + token_length > MAX_LINE_LENGTH) {
+                output.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                output.push(' ');
+                line_length += 1;
+            }
+            output.push_str(token);
+            line_length += token_length;
+            must_break = *ends_line;
+        }
+        output.push('\n');
+        output
+    }
+}
+
+/// Returns the move in SAN, with castling written with the letter O as export format requires
+fn export_san(board: &ChessBoard, mv: ChessMove) -> String {
+    let san = board.move_to_san(&mv);
+    if board.is_castling(mv) {
+        san.replace('0', "O")
+    } else {
+        san
+    }
+}
+
+/// Adds the moves of the line to the movetext. `board` is the position before the first move
+fn write_variation(
+    movetext: &mut Movetext,
+    start_board: &ChessBoard,
+    mut board: ChessBoard,
+    variation: &Variation,
+) {
+    for comment in variation.comments.iter() {
+        movetext.push_comment(comment);
+    }
+    // The move number is repeated for a Black move that starts a line or follows a comment or variation
+    let mut needs_number = true;
+    for pgn_move in variation.moves.iter() {
+        let move_number = full_move_number(start_board, &board);
+        if board.side_to_move() == White {
+            movetext.push(format!("{}.", move_number));
+        } else if needs_number {
+            movetext.push(format!("{}...", move_number));
+        }
+        movetext.push(export_san(&board, pgn_move.mv));
+        for nag in pgn_move.nags.iter() {
+            movetext.push(format!("${}", nag));
+        }
+        for comment in pgn_move.comments.iter() {
+            movetext.push_comment(comment);
+        }
+        for alternative in pgn_move.variations.iter() {
+            movetext.start_variation();
+            write_variation(movetext, start_board, board.clone(), alternative);
+            movetext.end_variation();
+        }
+        needs_number = !pgn_move.comments.is_empty() || !pgn_move.variations.is_empty();
+        board.do_move(pgn_move.mv);
+    }
+}
+
+/// Writes a tag pair, with quotes and backslashes in the value escaped
+fn write_tag(output: &mut String, name: &str, value: &str) {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    output.push_str(&format!("[{} \"{}\"]\n", name, value));
+}
+
+impl PgnGame {
+    /// Returns the game in PGN export format, ending with the line of the game termination marker.
+    ///
+    /// Tags from the seven-tag roster come first, with `?` for any that are missing,
+    /// followed by the other tags in their original order. The `Result` tag is taken from `result`,
+    /// and a game that does not start from the standard position gets `SetUp` and `FEN` tags.
+    pub fn to_pgn(&self) -> String {
+        let mut output = String::new();
+        for &name in SEVEN_TAG_ROSTER.iter() {
+            let value = match name {
+                "Result" => result_marker(self.result),
+                _ => self.tag(name).unwrap_or_else(|| default_tag_value(name)),
+            };
+            write_tag(&mut output, name, value);
+        }
+        let start_fen = self.start_board.to_fen();
+        if self.tag("FEN").is_none() && start_fen != ChessBoard::start_board().to_fen() {
+            write_tag(&mut output, "SetUp", "1");
+            write_tag(&mut output, "FEN", &start_fen);
+        }
+        for (name, value) in self.tags.iter() {
+            if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+                write_tag(&mut output, name, value);
+            }
+        }
+        output.push('\n');
+
+        let mut movetext = Movetext::default();
+        write_variation(
+            &mut movetext,
+            &self.start_board,
+            self.start_board.clone(),
+            &self.mainline,
+        );
+        movetext.push(result_marker(self.result).to_string());
+        output.push_str(&movetext.to_lines());
+        output
+    }
+}
+
+/// Writes games in PGN export format, separated by blank lines
+pub struct PgnWriter<W> {
+    writer: W,
+}
+
+impl PgnWriter<io::BufWriter<fs::File>> {
+    /// Creates a `.pgn` file to write to, replacing any existing file
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, pgn::Error> {
+        let file = fs::File::create(path.as_ref()).map_err(|err| {
+            pgn::Error::new_caused_by(
+                pgn::ErrorKind::IOError,
+                format!("Couldn't create PGN file {}", path.as_ref().display()),
+                err,
+            )
+        })?;
+        Ok(Self::new(io::BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> Self {
+        PgnWriter { writer }
+    }
+
+    /// Writes the game, followed by a blank line
+    pub fn write_game(&mut self, game: &PgnGame) -> Result<(), pgn::Error> {
+        writeln!(self.writer, "{}", game.to_pgn()).map_err(|err| {
+            pgn::Error::new_caused_by(pgn::ErrorKind::IOError, "Couldn't write game", err)
+        })
+    }
+
+    pub fn flush(&mut self) -> Result<(), pgn::Error> {
+        self.writer.flush().map_err(|err| {
+            pgn::Error::new_caused_by(pgn::ErrorKind::IOError, "Couldn't write games", err)
+        })
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
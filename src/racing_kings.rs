@@ -0,0 +1,81 @@
+//! Racing Kings, where both sides race their kings to the eighth rank and giving check is illegal.
+//! If White gets there first, Black has one move to reach the eighth rank as well and draw.
+
+use crate::chess_board::ChessBoard;
+use crate::chess_move::ChessMove;
+use crate::types::PieceType::*;
+use crate::variant::{Variant, VariantBoard};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color, GameResult};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RacingKings;
+
+pub type RacingKingsBoard = VariantBoard<RacingKings>;
+
+fn king_on_eighth_rank(board: &VariantBoard<RacingKings>, color: Color) -> bool {
+    board.board.king_pos(color).rank() == 0
+}
+
+/// Returns whether Black's king can reach the eighth rank with its next move
+fn black_can_equalize(board: &VariantBoard<RacingKings>) -> bool {
+    let mut moves = vec![];
+    board.board.generate_moves(&mut moves);
+    RacingKings::retain_legal(board, &mut moves);
+    let king_square = board.board.king_pos(Black);
+    moves
+        .iter()
+        .any(|mv| mv.from == king_square && mv.to.rank() == 0)
+}
+
+impl Variant for RacingKings {
+    type Undo = ();
+
+    const START_FEN: &'static str = "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1";
+
+    /// Removes the moves that give check
+    fn retain_legal(board: &VariantBoard<Self>, moves: &mut Vec<ChessMove>) {
+        moves.retain(|&mv| !board.board.gives_check(mv))
+    }
+
+    fn variant_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        match (
+            king_on_eighth_rank(board, White),
+            king_on_eighth_rank(board, Black),
+        ) {
+            (true, true) => Some(GameResult::Draw),
+            (false, true) => Some(GameResult::BlackWin),
+            (true, false) if board.side_to_move() == White || !black_can_equalize(board) => {
+                Some(GameResult::WhiteWin)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parses a FEN string. Both sides must have a king, and there can be no pawns or castling rights
+    fn from_fen(fen: &str) -> Result<VariantBoard<Self>, pgn::Error> {
+        let board = ChessBoard::from_fen(fen)?;
+        if [White, Black]
+            .iter()
+            .any(|&color| board.piece_bitboard(King, color).popcount() != 1)
+        {
+            return Err(pgn::Error::new(
+                pgn::ErrorKind::IllegalPosition,
+                format!("Racing Kings FEN {} must have one king for each side", fen),
+            ));
+        }
+        if !board.piece_type_bitboard(Pawn).is_empty()
+            || [White, Black]
+                .iter()
+                .any(|&color| board.can_castle_kingside(color) || board.can_castle_queenside(color))
+        {
+            return Err(pgn::Error::new(
+                pgn::ErrorKind::IllegalPosition,
+                format!("Racing Kings FEN {} has pawns or castling rights", fen),
+            ));
+        }
+        Ok(VariantBoard::new(board, RacingKings))
+    }
+}
@@ -0,0 +1,89 @@
+use crate::antichess::AntichessBoard;
+use crate::tests::tools;
+use crate::types::{Square, PieceType::*};
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+#[test]
+fn antichess_start_position_perft_test() {
+    let mut board = AntichessBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_067, 153_299]);
+}
+
+#[test]
+#[ignore]
+fn antichess_start_position_perft_test_long() {
+    let mut board = AntichessBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_067, 153_299, 2_732_672]);
+}
+
+#[test]
+fn captures_are_compulsory_test() {
+    let mut board = AntichessBoard::start_board();
+    for san in &["e3", "b5"] {
+        let mv = board.move_from_san(san).unwrap();
+        board.do_move(mv);
+    }
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves, vec![board.move_from_san("Bxb5").unwrap()]);
+    assert!(board.move_from_san("e4").is_err());
+}
+
+#[test]
+fn king_can_be_captured_test() {
+    let mut board = AntichessBoard::from_fen("8/8/8/8/8/8/5k2/4K3 w - - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves.len(), 1);
+    assert_eq!(board.move_to_san(&moves[0]), "Kxf2#");
+    board.do_move(moves[0]);
+    assert_eq!(board.game_result(), Some(GameResult::BlackWin));
+}
+
+#[test]
+fn promotion_to_king_test() {
+    let mut board = AntichessBoard::from_fen("8/3P4/8/8/8/8/8/7n w - - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves.len(), 5);
+    let mv = board.move_from_san("d8=K").unwrap();
+    assert_eq!(board.move_from_lan("d7d8k").unwrap(), mv);
+    assert_eq!(board.move_to_lan(&mv), "d7d8k");
+    assert_eq!(board.move_to_san(&mv), "d8=K");
+    assert_eq!(board.move_from_san(&board.move_to_san(&mv)).unwrap(), mv);
+    let reverse_move = board.do_move(mv);
+    assert_eq!(board.board()[Square::from_alg("d8").unwrap()].piece_type(), King);
+    board.reverse_move(reverse_move);
+    assert_eq!(board.board()[Square::from_alg("d7").unwrap()].piece_type(), Pawn);
+}
+
+#[test]
+fn stalemate_wins_test() {
+    // White's only pawn is blocked
+    let board = AntichessBoard::from_fen("8/8/8/8/8/4p3/4P3/8 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+
+    let board = AntichessBoard::from_fen("8/8/8/8/8/4p3/4P3/8 b - - 0 1").unwrap();
+    assert_eq!(board.game_result(), Some(GameResult::BlackWin));
+
+    let board = AntichessBoard::from_fen("8/8/8/8/8/4p3/3P4/8 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), None);
+}
+
+#[test]
+fn antichess_fen_test() {
+    assert_eq!(AntichessBoard::start_board().to_fen(),
+               "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+    assert!(AntichessBoard::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+    let fen = "8/8/2k5/8/3K4/8/1k6/8 b - - 3 20";
+    assert_eq!(AntichessBoard::from_fen(fen).unwrap().to_fen(), fen);
+}
+
+#[test]
+fn kingless_castling_is_illegal_test() {
+    let board = AntichessBoard::from_fen("8/8/8/8/8/4p3/3P4/8 w - - 0 1").unwrap();
+    assert!(board.move_from_san("O-O").is_err());
+    assert!(board.move_from_san("O-O-O").is_err());
+    assert!(AntichessBoard::start_board().move_from_san("O-O").is_err());
+}
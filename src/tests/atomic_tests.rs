@@ -0,0 +1,125 @@
+use crate::atomic::AtomicBoard;
+use crate::tests::tools;
+use crate::types::{Square, PieceType::*};
+use board_game_traits::board::{Board, GameResult};
+use board_game_traits::board::Color::{Black, White};
+use pgn_traits::pgn::PgnBoard;
+
+fn square(alg: &str) -> Square {
+    Square::from_alg(alg).unwrap()
+}
+
+#[test]
+fn atomic_start_position_perft_test() {
+    let mut board = AtomicBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_326]);
+}
+
+#[test]
+#[ignore]
+fn atomic_start_position_perft_test_long() {
+    let mut board = AtomicBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_326, 4_864_979]);
+}
+
+#[test]
+fn explosion_test() {
+    let mut board = AtomicBoard::from_fen("4k3/8/8/2npr3/3B4/2PN4/8/6K1 w - - 0 1").unwrap();
+    let mv = board.move_from_san("Bxe5").unwrap();
+    let reverse_move = board.do_move(mv);
+
+    // The bishop, the rook and the knight explode, the pawns survive
+    assert!(board.board()[square("d4")].is_empty());
+    assert!(board.board()[square("e5")].is_empty());
+    assert!(!board.board()[square("c5")].is_empty());
+    assert_eq!(board.board()[square("d5")].piece_type(), Pawn);
+    assert_eq!(board.board()[square("d3")].piece_type(), Knight);
+    assert_eq!(board.board()[square("c3")].piece_type(), Pawn);
+
+    let mut board2 = AtomicBoard::from_fen("4k3/8/8/2npr3/3B4/2PN4/8/6K1 w - - 0 1").unwrap();
+    board2.do_move(board2.move_from_san("Nxc5").unwrap());
+    assert!(board2.board()[square("d4")].is_empty());
+    assert!(board2.board()[square("c5")].is_empty());
+    assert_eq!(board2.board()[square("d5")].piece_type(), Pawn);
+
+    board.reverse_move(reverse_move);
+    let original = AtomicBoard::from_fen("4k3/8/8/2npr3/3B4/2PN4/8/6K1 w - - 0 1").unwrap();
+    assert_eq!(board, original);
+    assert_eq!(board.board().zobrist(), original.board().zobrist());
+}
+
+#[test]
+fn exploded_rook_loses_castling_rights_test() {
+    let mut board = AtomicBoard::from_fen("r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 0 1").unwrap();
+    board.do_move(board.move_from_san("Bxh1").unwrap());
+    assert!(!board.board().can_castle_kingside(White));
+    assert!(board.board().can_castle_queenside(White));
+    assert!(board.board().can_castle_kingside(Black));
+}
+
+#[test]
+fn king_cannot_capture_test() {
+    let board = AtomicBoard::from_fen("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1").unwrap();
+    assert!(board.move_from_san("Kxd2").is_err());
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(!moves.contains(&board.move_from_lan("e1d2").unwrap()));
+}
+
+#[test]
+fn adjacent_kings_test() {
+    // The kings are connected, so the rook on a2 does not give check
+    let board = AtomicBoard::from_fen("8/8/8/8/8/4k3/r3K3/r7 w - - 0 1").unwrap();
+    assert!(!board.is_in_check());
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves.len(), 4);
+    assert!(moves.contains(&board.move_from_san("Kd2").unwrap()));
+    assert!(moves.contains(&board.move_from_san("Kf3").unwrap()));
+    // Disconnecting the kings walks into the rook on the first rank
+    assert!(board.move_from_san("Ke1").is_err());
+}
+
+#[test]
+fn exploding_king_wins_test() {
+    let mut board = AtomicBoard::from_fen("4k3/4p3/8/8/8/8/8/3QK3 w - - 0 1").unwrap();
+    let mv = board.move_from_san("Qd7").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Qd7+");
+    board.do_move(mv);
+    assert_eq!(board.game_result(), None);
+    board.do_move(board.move_from_san("Kf8").unwrap());
+
+    let mv = board.move_from_san("Qxe7").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Qxe7#");
+    board.do_move(mv);
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.is_empty());
+}
+
+#[test]
+fn capture_exploding_own_king_is_illegal_test() {
+    let board = AtomicBoard::from_fen("4k3/8/8/8/8/8/3n4/2B1K3 w - - 0 1").unwrap();
+    assert!(board.move_from_san("Bxd2").is_err());
+
+    // Capturing next to both kings explodes both, which is illegal for the mover
+    let board = AtomicBoard::from_fen("8/8/8/8/8/3k4/3p4/2B1K3 w - - 0 1").unwrap();
+    assert!(board.move_from_san("Bxd2").is_err());
+}
+
+#[test]
+fn checkmate_test() {
+    let board = AtomicBoard::from_fen("7k/6Q1/5K2/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+    assert_eq!(board.side_to_move(), Black);
+}
+
+#[test]
+fn promotion_test() {
+    let board = AtomicBoard::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves.len(), 9);
+    assert_eq!(board.move_to_san(&board.move_from_lan("a7a8q").unwrap()), "a8=Q+");
+}
@@ -0,0 +1,53 @@
+use crate::attacks;
+use crate::bitboard::BitBoard;
+use crate::magic;
+use crate::types::Square;
+
+/// Simple xorshift generator, to get reproducible occupancies
+fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+#[test]
+fn magic_attacks_match_ray_walk_test() {
+    let mut state = 0x9e37_79b9_7f4a_7c15;
+    for _ in 0..200 {
+        // Sparse occupancies give longer rays, dense ones test the blockers
+        let occupied = BitBoard::from_u64(next_random(&mut state) & next_random(&mut state));
+        for square in (0..64).map(Square) {
+            assert_eq!(magic::rook_attacks(square, occupied),
+                       attacks::sliding_attacks(square, occupied, &attacks::ROOK_DIRECTIONS),
+                       "Wrong rook attacks from {} with occupancy {:?}", square, occupied);
+            assert_eq!(magic::bishop_attacks(square, occupied),
+                       attacks::sliding_attacks(square, occupied, &attacks::BISHOP_DIRECTIONS),
+                       "Wrong bishop attacks from {} with occupancy {:?}", square, occupied);
+        }
+    }
+}
+
+#[test]
+fn magic_masks_test() {
+    let square = |str| Square::from_alg(str).unwrap();
+    assert_eq!(magic::rook_mask(square("a1")).popcount(), 12);
+    assert_eq!(magic::rook_mask(square("e4")).popcount(), 10);
+    assert_eq!(magic::bishop_mask(square("a1")).popcount(), 6);
+    assert_eq!(magic::bishop_mask(square("e4")).popcount(), 9);
+    assert!(!magic::rook_mask(square("a1")).get(square("a8")));
+    assert!(!magic::rook_mask(square("a1")).get(square("h1")));
+}
+
+#[test]
+fn leaper_attacks_test() {
+    use board_game_traits::board::Color::{Black, White};
+    let square = |str| Square::from_alg(str).unwrap();
+    assert_eq!(attacks::knight_attacks(square("a1")),
+               BitBoard::empty().set(square("b3")).set(square("c2")));
+    assert_eq!(attacks::king_attacks(square("h8")).popcount(), 3);
+    assert_eq!(attacks::pawn_attacks(White, square("e4")),
+               BitBoard::empty().set(square("d5")).set(square("f5")));
+    assert_eq!(attacks::pawn_attacks(Black, square("a7")),
+               BitBoard::empty().set(square("b6")));
+}
@@ -0,0 +1,395 @@
+use crate::chess_board::{ChessBoard, Termination};
+use crate::types::{Piece, Square};
+use board_game_traits::board::{Board, ExtendedBoard, GameResult};
+use board_game_traits::board::Color::{Black, White};
+use pgn_traits::pgn::PgnBoard;
+
+/// Checks that the incrementally updated key matches the key of the same position built from scratch
+fn zobrist_matches_fen_prop(board: &ChessBoard) {
+    let fresh_board = ChessBoard::from_fen(&board.to_fen()).unwrap();
+    assert_eq!(board.zobrist(), fresh_board.zobrist(),
+               "Incremental zobrist key differs from a fresh key on board:{}", board);
+}
+
+#[test]
+fn zobrist_incremental_update_test() {
+    for fen in &["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                 "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                 "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"] {
+        let mut board = ChessBoard::from_fen(fen).unwrap();
+        let original_key = board.zobrist();
+        let mut moves = vec![];
+        board.generate_moves(&mut moves);
+        for mv in moves {
+            let reverse_move = board.do_move(mv);
+            zobrist_matches_fen_prop(&board);
+
+            let mut responses = vec![];
+            board.generate_moves(&mut responses);
+            for response in responses {
+                let reverse_response = board.do_move(response);
+                zobrist_matches_fen_prop(&board);
+                board.reverse_move(reverse_response);
+            }
+
+            board.reverse_move(reverse_move);
+            assert_eq!(board.zobrist(), original_key);
+        }
+    }
+}
+
+#[test]
+fn zobrist_transposition_test() {
+    let mut board1 = ChessBoard::start_board();
+    let mut board2 = ChessBoard::start_board();
+    for mv in &["Nf3", "Nf6", "Nc3", "Nc6"] {
+        let mv = board1.move_from_san(mv).unwrap();
+        board1.do_move(mv);
+    }
+    for mv in &["Nc3", "Nc6", "Nf3", "Nf6"] {
+        let mv = board2.move_from_san(mv).unwrap();
+        board2.do_move(mv);
+    }
+    assert_eq!(board1.zobrist(), board2.zobrist());
+    assert_eq!(board1.hash_board(), board2.hash_board());
+    assert_ne!(board1.zobrist(), ChessBoard::start_board().zobrist());
+}
+
+#[test]
+fn zobrist_null_move_test() {
+    let mut board = ChessBoard::from_fen(
+        "rnbqkbnr/ppp1pppp/8/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2").unwrap();
+    let mv = board.move_from_san("f5").unwrap();
+    board.do_move(mv);
+    let key = board.zobrist();
+
+    let reverse_null_move = board.do_null_move();
+    assert_ne!(board.zobrist(), key);
+    zobrist_matches_fen_prop(&board);
+    board.reverse_null_move(reverse_null_move);
+    assert_eq!(board.zobrist(), key);
+}
+
+fn play_sans(board: &mut ChessBoard, sans: &[&str]) {
+    for san in sans {
+        let mv = board.move_from_san(san).unwrap();
+        board.do_move(mv);
+    }
+}
+
+#[test]
+fn repetition_test() {
+    let knight_shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+    let mut board = ChessBoard::start_board();
+    board.enable_history();
+    assert!(board.is_repetition(1));
+    assert!(!board.is_repetition(2));
+
+    play_sans(&mut board, &knight_shuffle);
+    assert!(board.is_repetition(2));
+    assert!(!board.is_repetition(3));
+    assert_eq!(board.game_result(), None);
+
+    play_sans(&mut board, &knight_shuffle);
+    assert!(board.is_repetition(3));
+    assert_eq!(board.game_result(), Some(GameResult::Draw));
+
+    // Taking back a move forgets its position
+    let mv = board.move_from_san("Nf3").unwrap();
+    let reverse_move = board.do_move(mv);
+    assert!(board.is_repetition(3) && !board.is_repetition(4));
+    board.reverse_move(reverse_move);
+    assert!(board.is_repetition(3));
+
+    play_sans(&mut board, &knight_shuffle);
+    play_sans(&mut board, &knight_shuffle);
+    assert!(board.is_repetition(5));
+    assert!(!board.is_repetition(6));
+
+    // A pawn move cannot be undone, so later positions are never repetitions of earlier ones
+    play_sans(&mut board, &["e3", "e6"]);
+    play_sans(&mut board, &knight_shuffle);
+    assert!(board.is_repetition(2) && !board.is_repetition(3));
+}
+
+#[test]
+fn repetition_without_history_test() {
+    let mut board = ChessBoard::start_board();
+    for _ in 0..3 {
+        play_sans(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    }
+    assert!(!board.is_repetition(2));
+    assert_eq!(board.game_result(), None);
+}
+
+#[test]
+fn repetition_null_move_test() {
+    let mut board = ChessBoard::start_board();
+    board.enable_history();
+    let reverse_move = board.do_move(board.move_from_san("Nf3").unwrap());
+    let reverse_null_move = board.do_null_move();
+    let reverse_move2 = board.do_move(board.move_from_san("Ng1").unwrap());
+    let reverse_null_move2 = board.do_null_move();
+    // The start position, but only reached through null moves
+    assert_eq!(board.zobrist(), ChessBoard::start_board().zobrist());
+    assert!(!board.is_repetition(2));
+
+    board.reverse_null_move(reverse_null_move2);
+    board.reverse_move(reverse_move2);
+    board.reverse_null_move(reverse_null_move);
+    board.reverse_move(reverse_move);
+    assert_eq!(board, ChessBoard::start_board());
+    assert_eq!(board.half_move_clock, 0);
+
+    // The history is back to where it started
+    play_sans(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    assert!(board.is_repetition(2) && !board.is_repetition(3));
+}
+
+#[test]
+fn termination_test() {
+    let termination = |fen| ChessBoard::from_fen(fen).unwrap().termination();
+    assert_eq!(termination("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), None);
+    assert_eq!(termination("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+               Some(Termination::Checkmate { winner: Black }));
+    assert_eq!(termination("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Some(Termination::Stalemate));
+    assert_eq!(termination("8/8/4k3/8/2B5/8/4K3/8 w - - 0 1"), Some(Termination::InsufficientMaterial));
+    // Bishop against knight can still be mated
+    assert_eq!(termination("8/3n4/4k3/8/2B5/8/4K3/8 w - - 0 1"), None);
+    assert_eq!(termination("8/3b4/4k3/8/2B5/8/4K3/8 w - - 0 1"), Some(Termination::InsufficientMaterial));
+    assert_eq!(termination("8/3nn3/4k3/8/2B5/8/4K3/8 w - - 0 1"), None);
+    assert_eq!(termination("8/8/4k3/8/2R5/8/4K3/8 w - - 99 80"), None);
+    assert_eq!(termination("8/8/4k3/8/2R5/8/4K3/8 w - - 100 80"), Some(Termination::FiftyMoveRule));
+    assert_eq!(termination("8/8/4k3/8/2R5/8/4K3/8 w - - 150 80"), Some(Termination::SeventyFiveMoveRule));
+    // Checkmate on the last move before the move limit still counts
+    assert_eq!(termination("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80"), None);
+    let mut board = ChessBoard::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80").unwrap();
+    board.do_move(board.move_from_san("Ra8").unwrap());
+    assert_eq!(board.termination(), Some(Termination::Checkmate { winner: White }));
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+
+    let mut board = ChessBoard::start_board();
+    board.enable_history();
+    for _ in 0..2 {
+        play_sans(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    }
+    assert_eq!(board.termination(), Some(Termination::ThreefoldRepetition));
+    assert!(Termination::ThreefoldRepetition.is_claimable());
+    for _ in 0..2 {
+        play_sans(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    }
+    assert_eq!(board.termination(), Some(Termination::FivefoldRepetition));
+    assert!(!Termination::FivefoldRepetition.is_claimable());
+    assert_eq!(board.game_result(), Some(GameResult::Draw));
+}
+
+#[test]
+fn san_check_suffix_test() {
+    let san = |fen, san| {
+        let board = ChessBoard::from_fen(fen).unwrap();
+        board.move_to_san(&board.move_from_san(san).unwrap())
+    };
+    // Drawn by insufficient material, but not checkmate
+    assert_eq!(san("7k/8/8/6N1/8/8/8/K7 w - - 0 1", "Nf7"), "Nf7+");
+    // Reaches the fifty-move rule, and the king can take the queen
+    assert_eq!(san("7k/8/8/8/8/8/6Q1/K7 w - - 99 80", "Qg7"), "Qg7+");
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80", "Ra8"), "Ra8#");
+    assert_eq!(san("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "Qh4"), "Qh4#");
+}
+
+#[test]
+fn play_past_fifty_move_rule_test() {
+    let mut board = ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    play_sans(&mut board, &["Ra2", "Kd8"]);
+    assert_eq!(board.half_move_clock, 101);
+    assert_eq!(board.termination(), Some(Termination::FiftyMoveRule));
+    assert!(Termination::FiftyMoveRule.is_claimable());
+
+    // The draw is only claimable, so the game may go on
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(!moves.is_empty());
+    play_sans(&mut board, &["Ra3"]);
+    assert_eq!(board.half_move_clock, 102);
+
+    // The clock saturates instead of overflowing
+    let mut board = ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 255 200").unwrap();
+    let reverse_move = board.do_move(board.move_from_san("Ra2").unwrap());
+    assert_eq!(board.half_move_clock, 255);
+    board.reverse_move(reverse_move);
+    assert_eq!(board.half_move_clock, 255);
+}
+
+#[test]
+fn can_mate_test() {
+    let can_mate = |fen, color| ChessBoard::from_fen(fen).unwrap().can_mate(color);
+    assert!(!can_mate("8/8/4k3/8/8/8/4K3/8 w - - 0 1", White));
+    assert!(!can_mate("8/8/4k3/8/2N5/8/4K3/8 w - - 0 1", White));
+    assert!(can_mate("8/8/4k3/8/2R5/8/4K3/8 w - - 0 1", White));
+    assert!(can_mate("8/8/4k3/8/2P5/8/4K3/8 w - - 0 1", White));
+    // The opponent's pawn may block its own king
+    assert!(can_mate("8/3p4/4k3/8/2N5/8/4K3/8 w - - 0 1", White));
+    assert!(can_mate("8/8/4k3/8/2N5/8/2N1K3/8 w - - 0 1", White));
+    assert!(can_mate("8/8/4k3/8/2N5/8/2B1K3/8 w - - 0 1", White));
+    // Bishops on the same color, against a bare king or a bishop on the same color
+    assert!(!can_mate("8/8/4k3/8/2B5/8/4K1B1/8 w - - 0 1", White));
+    assert!(!can_mate("8/3b4/4k3/8/2B5/8/4K3/8 w - - 0 1", White));
+    // An opposite-colored bishop or a knight can block the other color
+    assert!(can_mate("8/4b3/4k3/8/2B5/8/4K3/8 w - - 0 1", White));
+    assert!(can_mate("8/3n4/4k3/8/2B5/8/4K3/8 w - - 0 1", White));
+    assert!(can_mate("8/8/4k3/8/2B5/8/3BK3/8 w - - 0 1", White));
+}
+
+#[test]
+fn insufficient_material_test() {
+    let insufficient = |fen| ChessBoard::from_fen(fen).unwrap().is_insufficient_material();
+    assert!(insufficient("8/8/4k3/8/8/8/4K3/8 w - - 0 1"));
+    assert!(insufficient("8/8/4k3/8/2N5/8/4K3/8 w - - 0 1"));
+    assert!(insufficient("8/8/4k3/8/2B5/8/4K3/8 w - - 0 1"));
+    assert!(insufficient("b7/3b4/4k3/8/2B5/8/4K1B1/8 w - - 0 1"));
+    assert!(!insufficient("8/3n4/4k3/8/2B5/8/4K3/8 w - - 0 1"));
+    assert!(!insufficient("8/3n4/4k3/8/2N5/8/4K3/8 w - - 0 1"));
+    assert!(!insufficient("8/4b3/4k3/8/2B5/8/4K3/8 w - - 0 1"));
+    assert!(!insufficient("8/8/4k3/8/2P5/8/4K3/8 w - - 0 1"));
+}
+
+#[test]
+fn dead_position_test() {
+    // Neither king can get through the pawns, or attack any of them
+    let board = ChessBoard::from_fen("k7/8/1p1p1p1p/pPpPpPpP/P1P1P1P1/8/8/7K w - - 0 1").unwrap();
+    assert!(!board.is_insufficient_material());
+    assert!(board.is_dead_position(10_000));
+    assert!(!board.is_dead_position(10));
+
+    // The h-pawn can still advance
+    let board = ChessBoard::from_fen("k7/8/1p1p1p2/pPpPpPpP/P1P1P1P1/8/8/7K w - - 0 1").unwrap();
+    assert!(!board.is_dead_position(10_000));
+
+    assert!(!ChessBoard::start_board().is_dead_position(1000));
+    assert!(ChessBoard::from_fen("8/8/4k3/8/8/8/4K3/8 w - - 0 1").unwrap().is_dead_position(0));
+}
+
+#[test]
+fn chess960_start_board_test() {
+    let back_rank = |index| {
+        let board = ChessBoard::chess960_start_board(index).unwrap();
+        board.to_fen().split('/').next().unwrap().to_string()
+    };
+    assert_eq!(back_rank(0), "bbqnnrkr");
+    assert_eq!(back_rank(518), "rnbqkbnr");
+    assert_eq!(back_rank(959), "rkrnnqbb");
+    assert!(ChessBoard::chess960_start_board(960).is_none());
+
+    let standard = ChessBoard::chess960_start_board(518).unwrap();
+    assert!(standard.is_chess960());
+    assert_eq!(standard.to_fen(), ChessBoard::start_board().to_fen());
+
+    let mut back_ranks: Vec<String> = (0..960).map(back_rank).collect();
+    back_ranks.sort();
+    back_ranks.dedup();
+    assert_eq!(back_ranks.len(), 960);
+}
+
+#[test]
+fn chess960_fen_test() {
+    let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+    let board = ChessBoard::from_fen(fen).unwrap();
+    assert!(board.is_chess960());
+    assert_eq!(board.castling_rook_file(White, true), 7);
+    assert_eq!(board.castling_rook_file(White, false), 5);
+    // X-FEN uses KQkq for the outermost rooks
+    assert_eq!(board.to_fen(), "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9");
+    assert_eq!(board.to_shredder_fen(), fen);
+    assert_eq!(ChessBoard::from_fen(&board.to_fen()).unwrap(), board);
+
+    // The b-rook castles, and is not the outermost rook on its side
+    let board = ChessBoard::from_fen("rrk5/8/8/8/8/8/8/RRK5 w Bb - 0 1").unwrap();
+    assert_eq!(board.to_fen(), "rrk5/8/8/8/8/8/8/RRK5 w Bb - 0 1");
+    assert_eq!(ChessBoard::from_fen(&board.to_fen()).unwrap(), board);
+
+    // The standard position does not need Chess960 mode
+    let board = ChessBoard::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+    assert!(!board.is_chess960());
+    assert_eq!(board, ChessBoard::start_board());
+
+    assert!(ChessBoard::from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").is_err());
+    assert!(ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w B - 0 1").is_err());
+    assert!(ChessBoard::from_fen("4k3/8/8/8/8/8/4K3/R7 w Q - 0 1").is_err());
+}
+
+#[test]
+fn chess960_castling_test() {
+    // The king and rook swap squares when castling kingside
+    let mut board = ChessBoard::from_fen("5k1r/8/8/8/8/8/8/5KR1 w Gh - 0 1").unwrap();
+    let original_board = board.clone();
+    let mv = board.move_from_san("0-0").unwrap();
+    assert_eq!(board.move_to_lan(&mv), "f1g1");
+    // The rook lands on the f-file, giving check
+    assert_eq!(board.move_to_san(&mv), "0-0+");
+    let reverse_move = board.do_move(mv);
+    assert_eq!(board.to_fen(), "5k1r/8/8/8/8/8/8/5RK1 b k - 1 2");
+    assert_eq!(board.zobrist(), ChessBoard::from_fen(&board.to_fen()).unwrap().zobrist());
+    board.reverse_move(reverse_move);
+    assert_eq!(board, original_board);
+    assert_eq!(board.zobrist(), original_board.zobrist());
+
+    // The king stays on c1, but castling is illegal because the b1 rook was shielding it
+    let board = ChessBoard::from_fen("r3k3/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap();
+    assert!(board.move_from_san("0-0-0").is_err());
+
+    // Moving a castling rook off a non-corner square loses its castling rights
+    let mut board = ChessBoard::from_fen("1rk4r/8/8/8/8/8/8/1RK4R w BHbh - 0 1").unwrap();
+    play_sans(&mut board, &["Rb2", "Rb7"]);
+    assert_eq!(board.to_shredder_fen(), "2k4r/1r6/8/8/8/8/1R6/2K4R w Hh - 2 3");
+
+    // After losing its castling rights, the board equals the same position read from FEN
+    let mut board = ChessBoard::from_fen("1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1").unwrap();
+    play_sans(&mut board, &["Ke2", "Ke7", "Ke1", "Ke8"]);
+    let fen_board = ChessBoard::from_fen(&board.to_fen()).unwrap();
+    assert_eq!(board.zobrist(), fen_board.zobrist());
+    assert_eq!(board, fen_board);
+}
+
+#[test]
+fn double_chess960_start_board_test() {
+    let board = ChessBoard::double_chess960_start_board(518, 0).unwrap();
+    assert_eq!(board.to_fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(board.castling_rook_file(White, false), 0);
+    assert_eq!(board.castling_rook_file(Black, false), 5);
+    assert_eq!(ChessBoard::double_chess960_start_board(7, 7), ChessBoard::chess960_start_board(7));
+    assert!(ChessBoard::double_chess960_start_board(0, 960).is_none());
+}
+
+#[test]
+fn asymmetric_castling_test() {
+    // Each side castles with rooks on different files
+    let fen = "1r2k1r1/8/8/8/8/8/8/R2K3R w HAgb - 0 1";
+    let mut board = ChessBoard::from_fen(fen).unwrap();
+    assert!(board.is_chess960());
+    assert_eq!(board.to_shredder_fen(), fen);
+    assert_eq!(board.to_fen(), "1r2k1r1/8/8/8/8/8/8/R2K3R w KQkq - 0 1");
+    assert_eq!(ChessBoard::from_fen(&board.to_fen()).unwrap(), board);
+
+    play_sans(&mut board, &["0-0-0", "0-0"]);
+    assert_eq!(board.to_fen(), "1r3rk1/8/8/8/8/8/8/2KR3R w - - 2 3");
+
+    // Capturing a castling rook on a non-corner square removes the opponent's castling rights
+    let mut board = ChessBoard::from_fen("1r2k1r1/8/8/8/8/8/8/1R1K2R1 w BGbg - 0 1").unwrap();
+    play_sans(&mut board, &["Rxb8"]);
+    assert_eq!(board.to_shredder_fen(), "1R2k1r1/8/8/8/8/8/8/3K2R1 b Gg - 0 2");
+}
+
+#[test]
+fn read_only_accessors_test() {
+    let mut board = ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert_eq!(board.squares()[7][4], Piece::from_letter('K').unwrap());
+    assert_eq!(board.squares()[0][4], Piece::from_letter('k').unwrap());
+    assert!(board.can_castle_queenside(White));
+    assert!(!board.can_castle_kingside(White));
+    assert!(!board.can_castle_queenside(Black));
+    assert_eq!(board.en_passant_square(), None);
+
+    board.set_piece(Square::from_ints(3, 3), Piece::from_letter('Q').unwrap());
+    assert_eq!(board.squares()[3][3], board[Square::from_ints(3, 3)]);
+    zobrist_matches_fen_prop(&board);
+}
@@ -0,0 +1,116 @@
+use crate::chess_board::ChessBoard;
+use crate::chess_game::ChessGame;
+use crate::pgn_reader::PgnReader;
+use crate::tests::tools;
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+fn play_san(game: &mut ChessGame, moves: &[&str]) {
+    for san in moves {
+        let mv = game.board().move_from_san(san).unwrap();
+        game.play(mv);
+    }
+}
+
+#[test]
+fn undo_redo_test() {
+    let mut game = ChessGame::start_board();
+    assert_eq!(game.undo(), None);
+    assert_eq!(game.redo(), None);
+
+    play_san(&mut game, &["e4", "e5", "Nf3"]);
+    let after_e5 = ChessBoard::from_fen(
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 3").unwrap();
+    let nf3 = after_e5.move_from_san("Nf3").unwrap();
+
+    assert_eq!(game.undo(), Some(nf3));
+    assert_eq!(game.ply(), 2);
+    assert_eq!(game.num_moves(), 3);
+    assert_eq!(game.board().to_fen(), after_e5.to_fen());
+    assert_eq!(game.redo(), Some(nf3));
+    assert_eq!(game.redo(), None);
+    assert_eq!(game.moves().len(), 3);
+
+    // Playing a different move forgets the undone moves
+    game.undo();
+    play_san(&mut game, &["Nc3"]);
+    assert_eq!(game.num_moves(), 3);
+    assert_eq!(game.redo(), None);
+    assert_ne!(game.moves()[2], nf3);
+}
+
+#[test]
+fn go_to_ply_test() {
+    let mut game = ChessGame::start_board();
+    play_san(&mut game, &["d4", "Nf6", "c4", "e6", "Nc3", "Bb4"]);
+    let final_fen = game.board().to_fen();
+
+    assert!(game.go_to_ply(0));
+    assert_eq!(game.board().to_fen(), game.start_position().to_fen());
+    assert!(game.moves().is_empty());
+    assert!(game.go_to_ply(3));
+    assert_eq!(game.board().side_to_move(), board_game_traits::board::Color::Black);
+    assert!(!game.go_to_ply(7));
+    assert_eq!(game.ply(), 3);
+    assert!(game.go_to_ply(6));
+    assert_eq!(game.board().to_fen(), final_fen);
+}
+
+#[test]
+fn repetition_test() {
+    let mut game = ChessGame::start_board();
+    play_san(&mut game, &["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1"]);
+    assert_eq!(game.game_result(), None);
+    play_san(&mut game, &["Ng8"]);
+    assert_eq!(game.game_result(), Some(GameResult::Draw));
+
+    game.undo();
+    assert_eq!(game.game_result(), None);
+    game.redo();
+    assert_eq!(game.game_result(), Some(GameResult::Draw));
+}
+
+#[test]
+fn result_test() {
+    let mut game = ChessGame::start_board();
+    play_san(&mut game, &["e4"]);
+    game.set_result(Some(GameResult::WhiteWin));
+    assert_eq!(game.game_result(), Some(GameResult::WhiteWin));
+    game.set_result(None);
+    assert_eq!(game.game_result(), None);
+}
+
+#[test]
+fn perft_test() {
+    let mut game = ChessGame::start_board();
+    tools::perft_check_answers(&mut game, &[1, 20, 400, 8_902]);
+    assert_eq!(game.ply(), 0);
+}
+
+#[test]
+fn pgn_game_test() {
+    let input = "[Event \"Casual game\"]\n[White \"A\"]\n[Black \"B\"]\n[Result \"0-1\"]\n\n\
+                 1. e4 {Best by test} e5 2. Nf3 (2. f4) Nc6 0-1\n";
+    let pgn_game = PgnReader::new(input.as_bytes()).read_game().unwrap().unwrap();
+    let mut game = ChessGame::from_pgn_game(&pgn_game);
+    assert_eq!(game.ply(), 4);
+    assert_eq!(game.tag("Event"), Some("Casual game"));
+    assert_eq!(game.tags().len(), 4);
+    // The game was resigned, as the position has not ended
+    assert_eq!(game.game_result(), Some(GameResult::BlackWin));
+
+    game.set_tag("Event", "Club game");
+    game.go_to_ply(2);
+    let exported = game.to_pgn_game();
+    assert_eq!(exported.tag("Event"), Some("Club game"));
+    assert_eq!(exported.mainline.moves.len(), 2);
+    assert!(exported.to_pgn().ends_with("\n\n1. e4 e5 0-1\n"), "{}", exported.to_pgn());
+
+    // A checkmate needs no separate result
+    let input = "1. f3 e5 2. g4 Qh4# 0-1";
+    let pgn_game = PgnReader::new(input.as_bytes()).read_game().unwrap().unwrap();
+    let mut game = ChessGame::from_pgn_game(&pgn_game);
+    assert_eq!(game.game_result(), Some(GameResult::BlackWin));
+    game.undo();
+    assert_eq!(game.game_result(), None);
+}
@@ -0,0 +1,150 @@
+use crate::crazyhouse::CrazyhouseBoard;
+use crate::chess_move::ChessMove;
+use crate::tests::tools;
+use crate::types::{Square, PieceType::*};
+use board_game_traits::board::{Board, GameResult};
+use board_game_traits::board::Color::{Black, White};
+use pgn_traits::pgn::PgnBoard;
+
+fn play_sans(board: &mut CrazyhouseBoard, sans: &[&str]) {
+    for san in sans {
+        let mv = board.move_from_san(san).unwrap();
+        board.do_move(mv);
+    }
+}
+
+#[test]
+fn crazyhouse_start_position_perft_test() {
+    let mut board = CrazyhouseBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_281]);
+}
+
+#[test]
+#[ignore]
+fn crazyhouse_start_position_perft_test_long() {
+    let mut board = CrazyhouseBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_281, 4_888_832]);
+}
+
+#[test]
+fn crazyhouse_perft_test() {
+    let mut board = CrazyhouseBoard::from_fen("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1").unwrap();
+    tools::perft_check_answers(&mut board, &[1, 301, 75_353]);
+
+    let mut board = CrazyhouseBoard::from_fen(
+        "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1").unwrap();
+    tools::perft_check_answers(&mut board, &[1, 42, 1_347, 58_057]);
+
+    // The promoted queen returns to the hand as a pawn when captured
+    let mut board = CrazyhouseBoard::from_fen("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1").unwrap();
+    tools::perft_check_answers(&mut board, &[1, 20, 360, 5_445, 132_758]);
+}
+
+#[test]
+fn crazyhouse_fen_test() {
+    let fen = "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQ~K2R[QNPPbp] b KQkq - 0 1";
+    let board = CrazyhouseBoard::from_fen(fen).unwrap();
+    assert_eq!(board.to_fen(), fen);
+    assert_eq!(board.pocket(White, Pawn), 2);
+    assert_eq!(board.pocket(Black, Bishop), 1);
+    assert_eq!(board.pocket(Black, Queen), 0);
+    assert!(board.promoted().get(Square::from_alg("d1").unwrap()));
+
+    assert_eq!(CrazyhouseBoard::from_fen("4k3/8/8/8/8/8/8/4K3/Nn w - - 0 1").unwrap().to_fen(),
+               "4k3/8/8/8/8/8/8/4K3[Nn] w - - 0 1");
+    assert_eq!(CrazyhouseBoard::start_board().to_fen(),
+               "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1");
+    assert!(CrazyhouseBoard::from_fen("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1").is_err());
+    assert!(CrazyhouseBoard::from_fen("4k3/8/8/8/8/8/8/4K3[N w - - 0 1").is_err());
+}
+
+#[test]
+fn crazyhouse_malformed_fen_test() {
+    for fen in &[
+        "8/8/8/8/8/8/8/99~[] w - - 0 1",
+        "8/8/8/8/8/8/8/8/8/8/Q~[] w - - 0 1",
+        "~4k3/8/8/8/8/8/8/4K3[] w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K399999999999999999999999999999999Q~[] w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3~~[] w - - 0 1",
+    ] {
+        assert!(CrazyhouseBoard::from_fen(fen).is_err(), "Parsed {}", fen);
+    }
+}
+
+#[test]
+fn crazyhouse_captures_and_drops_test() {
+    let mut board = CrazyhouseBoard::start_board();
+    play_sans(&mut board, &["e4", "d5", "exd5", "Qxd5", "Nc3"]);
+    assert_eq!(board.pocket(White, Pawn), 1);
+    assert_eq!(board.pocket(Black, Pawn), 1);
+
+    let mv = board.move_from_san("P@e4").unwrap();
+    assert_eq!(mv, ChessMove::new_drop(Square::from_alg("e4").unwrap(), Pawn));
+    assert_eq!(board.move_from_san("@e4").unwrap(), mv);
+    assert_eq!(board.move_from_lan("P@e4").unwrap(), mv);
+    assert_eq!(board.move_to_lan(&mv), "P@e4");
+    assert_eq!(board.move_to_san(&mv), "P@e4");
+    assert!(board.move_from_san("N@e4").is_err());
+    assert!(board.move_from_san("P@e1").is_err());
+
+    let original_board = board.clone();
+    let reverse_move = board.do_move(mv);
+    assert_eq!(board.pocket(Black, Pawn), 0);
+    assert_eq!(board.to_fen(), "rnb1kbnr/ppp1pppp/8/3q4/4p3/2N5/PPPP1PPP/R1BQKBNR[P] w KQkq - 2 7");
+    board.reverse_move(reverse_move);
+    assert_eq!(board, original_board);
+}
+
+#[test]
+fn crazyhouse_promoted_piece_test() {
+    let mut board = CrazyhouseBoard::from_fen("4k3/1P6/8/8/8/8/4K3/r7[] w - - 0 1").unwrap();
+    play_sans(&mut board, &["b8=Q+", "Kd7", "Qb1"]);
+    assert!(board.promoted().get(Square::from_alg("b1").unwrap()));
+    assert_eq!(board.to_fen(), "8/3k4/8/8/8/8/4K3/rQ~6[] b - - 2 4");
+
+    play_sans(&mut board, &["Rxb1"]);
+    assert_eq!(board.pocket(Black, Pawn), 1);
+    assert_eq!(board.pocket(Black, Queen), 0);
+    assert!(board.promoted().is_empty());
+}
+
+#[test]
+fn crazyhouse_check_test() {
+    // A drop can block a check from a distance
+    let board = CrazyhouseBoard::from_fen("4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_drops(&mut moves);
+    assert_eq!(moves.len(), 3);
+
+    // Mate on the board, but a drop can block it
+    let board = CrazyhouseBoard::from_fen("6rk/6pp/8/8/8/8/8/R6K[n] b - - 0 1").unwrap();
+    assert_eq!(board.game_result(), None);
+    let board = CrazyhouseBoard::from_fen("6rk/6pp/8/8/8/8/8/R6K[] w - - 0 1").unwrap();
+    let mv = board.move_from_san("Ra8").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Ra8");
+    let board = CrazyhouseBoard::from_fen("7k/6pp/8/8/8/8/8/R6K[] w - - 0 1").unwrap();
+    let mv = board.move_from_san("Ra8").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Ra8#");
+    let mut board = board;
+    board.do_move(mv);
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+
+    // A dropped knight can deliver mate
+    let board = CrazyhouseBoard::from_fen("6rk/6pp/8/8/8/8/8/7K[N] w - - 0 1").unwrap();
+    let mv = board.move_from_san("N@f7").unwrap();
+    assert_eq!(board.move_to_san(&mv), "N@f7#");
+
+    // No drops help in double check
+    let board = CrazyhouseBoard::from_fen("4k3/8/8/8/1b6/8/4r3/4K3[QRBNP] w - - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_drops(&mut moves);
+    assert!(moves.is_empty());
+}
+
+#[test]
+fn crazyhouse_no_fifty_move_rule_test() {
+    let mut board = CrazyhouseBoard::from_fen("4k3/8/8/8/8/8/8/R3K3[Nn] w - - 120 90").unwrap();
+    play_sans(&mut board, &["N@c3", "N@c6", "Ra2"]);
+    assert_eq!(board.board().half_move_clock, 123);
+    assert_eq!(board.game_result(), None);
+}
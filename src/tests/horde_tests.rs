@@ -0,0 +1,89 @@
+use crate::horde::HordeBoard;
+use crate::tests::tools;
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+#[test]
+fn horde_start_position_perft_test() {
+    let mut board = HordeBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 8, 128, 1_274, 23_310]);
+}
+
+#[test]
+#[ignore]
+fn horde_start_position_perft_test_long() {
+    let mut board = HordeBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 8, 128, 1_274, 23_310, 265_223]);
+}
+
+#[test]
+fn horde_fen_test() {
+    let board = HordeBoard::start_board();
+    assert_eq!(board.to_fen(),
+               "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1");
+    let fen = "4k3/8/8/8/8/8/8/P7 w - - 0 1";
+    assert_eq!(HordeBoard::from_fen(fen).unwrap().to_fen(), fen);
+}
+
+#[test]
+fn first_rank_double_push_test() {
+    let mut board = HordeBoard::from_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves.len(), 2);
+    let mv = board.move_from_san("a3").unwrap();
+    board.do_move(mv);
+    // A double push from the first rank cannot be captured en passant
+    assert_eq!(board.board().en_passant_square(), None);
+
+    let mut board = HordeBoard::from_fen("4k3/8/8/8/1p6/8/P7/8 w - - 0 1").unwrap();
+    board.do_move(board.move_from_san("a4").unwrap());
+    assert!(board.move_from_san("bxa3").is_ok());
+}
+
+#[test]
+fn capturing_every_piece_wins_test() {
+    let mut board = HordeBoard::from_fen("4k3/8/8/8/8/8/1r6/P7 b - - 0 1").unwrap();
+    assert_eq!(board.game_result(), None);
+    let mv = board.move_from_san("Rb1").unwrap();
+    board.do_move(mv);
+    board.do_move(board.move_from_san("a2").unwrap());
+    let mv = board.move_from_san("Rb2").unwrap();
+    board.do_move(mv);
+    board.do_move(board.move_from_san("a3").unwrap());
+    board.do_move(board.move_from_san("Rb3").unwrap());
+    board.do_move(board.move_from_san("a4").unwrap());
+    let mv = board.move_from_san("Ra3").unwrap();
+    board.do_move(mv);
+    board.do_move(board.move_from_san("a5").unwrap());
+    let mv = board.move_from_san("Rxa5").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Rxa5");
+    board.do_move(mv);
+    assert_eq!(board.game_result(), Some(GameResult::BlackWin));
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.is_empty());
+}
+
+#[test]
+fn white_blocked_is_stalemate_test() {
+    let board = HordeBoard::from_fen("4k3/8/8/8/8/p7/P7/8 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), Some(GameResult::Draw));
+}
+
+#[test]
+fn white_checkmates_test() {
+    let mut board = HordeBoard::from_fen("7k/5P2/6PP/8/8/8/8/8 w - - 0 1").unwrap();
+    let mv = board.move_from_san("f8=Q").unwrap();
+    assert_eq!(board.move_to_san(&mv), "f8=Q#");
+    board.do_move(mv);
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+}
+
+#[test]
+fn kingless_castling_is_illegal_test() {
+    let board = HordeBoard::start_board();
+    assert!(board.move_from_san("O-O").is_err());
+    assert!(board.move_from_san("O-O-O").is_err());
+    assert!(board.move_from_san("0-0").is_err());
+}
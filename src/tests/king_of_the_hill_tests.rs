@@ -0,0 +1,42 @@
+use crate::king_of_the_hill::KingOfTheHillBoard;
+use crate::tests::tools;
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+#[test]
+fn king_of_the_hill_start_position_perft_test() {
+    let mut board = KingOfTheHillBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_281]);
+}
+
+#[test]
+fn king_on_hill_wins_test() {
+    let mut board = KingOfTheHillBoard::from_fen("4k3/8/8/8/8/3K4/8/8 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), None);
+    let mv = board.move_from_san("Ke4").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Ke4");
+    let reverse_move = board.do_move(mv);
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.is_empty());
+    board.reverse_move(reverse_move);
+
+    board.do_move(board.move_from_san("Kc3").unwrap());
+    board.do_move(board.move_from_san("Kd7").unwrap());
+    board.do_move(board.move_from_san("Kc2").unwrap());
+    board.do_move(board.move_from_san("Kd6").unwrap());
+    board.do_move(board.move_from_san("Kc3").unwrap());
+    board.do_move(board.move_from_san("Kd5").unwrap());
+    assert_eq!(board.game_result(), Some(GameResult::BlackWin));
+}
+
+#[test]
+fn king_of_the_hill_material_test() {
+    // Bare kings are not a draw, since either king can reach the hill
+    let board = KingOfTheHillBoard::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), None);
+
+    let board = KingOfTheHillBoard::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(board.game_result(), Some(GameResult::Draw));
+}
@@ -1,3 +1,31 @@
 #[cfg(test)]
+mod antichess_tests;
+#[cfg(test)]
+mod atomic_tests;
+#[cfg(test)]
+mod attacks_tests;
+#[cfg(test)]
+mod chess_board_tests;
+#[cfg(test)]
+mod chess_game_tests;
+#[cfg(test)]
+mod crazyhouse_tests;
+#[cfg(test)]
+mod horde_tests;
+#[cfg(test)]
+mod king_of_the_hill_tests;
+#[cfg(test)]
 mod move_gen_tests;
-mod tools;
\ No newline at end of file
+#[cfg(test)]
+mod pgn_reader_tests;
+#[cfg(test)]
+mod pgn_writer_tests;
+#[cfg(test)]
+mod polyglot_tests;
+#[cfg(test)]
+mod racing_kings_tests;
+#[cfg(test)]
+mod three_check_tests;
+#[cfg(test)]
+mod variant_tests;
+mod tools;
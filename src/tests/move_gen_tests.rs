@@ -1,5 +1,7 @@
 use crate::chess_board::ChessBoard;
 use crate::chess_move::ChessMove;
+use crate::move_list::MoveList;
+use crate::bitboard::BitBoard;
 use board_game_traits::board::Board;
 
 use pgn_traits::pgn::PgnBoard;
@@ -45,51 +47,51 @@ fn respond_to_checks() {
     let board1 = ChessBoard::from_fen("rnbqkbnr/ppNppppp/8/8/8/8/PPPPPPPP/R1BQKBNR b KQkq - 0 1")
         .unwrap();
     assert!(move_gen::is_attacked(&board1, board1.king_pos(board1.side_to_move())),
-            format!("Error: King should be under attack here:\n{}", board1));
+            "Error: King should be under attack here:\n{}", board1);
     board1.generate_moves(&mut legal_moves);
     assert!(legal_moves.len() == 1,
-            format!("Found {} legal moves, expected 1. Board:\n{}", legal_moves.len(), board1));
+            "Found {} legal moves, expected 1. Board:\n{}", legal_moves.len(), board1);
     legal_moves.clear();
 
     let board2 = ChessBoard::from_fen("r1bqkb1r/pppppppp/5N2/8/3n4/8/PPPPPPPP/R1BQKBNR b KQkq - 0 1")
         .unwrap();
     assert!(move_gen::is_attacked(&board2, board2.king_pos(board2.side_to_move())),
-            format!("Error: King should be under attack here:\n{}", board2));
+            "Error: King should be under attack here:\n{}", board2);
     board2.generate_moves(&mut legal_moves);
     assert!(legal_moves.len() == 2,
-            format!("Found {} legal moves, expected 2. Board:\n{}", legal_moves.len(), board2));
+            "Found {} legal moves, expected 2. Board:\n{}", legal_moves.len(), board2);
     legal_moves.clear();
 
     let board3 = ChessBoard::from_fen("r1bqkb1r/pppppppp/5N2/8/3n4/4P3/PPPP1PPP/R1BQKBNR b KQkq - 0 1")
         .unwrap();
     assert!(move_gen::is_attacked(&board3, board3.king_pos(board3.side_to_move())),
-            format!("Error: King should be under attack here:\n{}", board3));
+            "Error: King should be under attack here:\n{}", board3);
     board3.generate_moves(&mut legal_moves);
     assert!(legal_moves.len() == 2,
-            format!("Found {} legal moves, expected 2. Board:\n{}", legal_moves.len(), board3));
+            "Found {} legal moves, expected 2. Board:\n{}", legal_moves.len(), board3);
     legal_moves.clear();
 
     let board4 = ChessBoard::from_fen("r1bqkb1r/pppp1ppp/5p2/7Q/3n4/4P3/PPPP1PPP/R1B1KBNR b KQkq - 0 1")
         .unwrap();
     assert!(!move_gen::is_attacked(&board4, board4.king_pos(board4.side_to_move())),
-            format!("Error: King should not be under attack here:\n{}", board4));
+            "Error: King should not be under attack here:\n{}", board4);
     board4.generate_moves(&mut legal_moves);
     assert!(legal_moves.len() == 29,
-            format!("Found {} legal moves, expected 29. Board:\n{}", legal_moves.len(), board4));
+            "Found {} legal moves, expected 29. Board:\n{}", legal_moves.len(), board4);
     legal_moves.clear();
 
     let board5 = ChessBoard::from_fen("k7/Q6K/8/8/8/8/8/8 b - - 0 1").unwrap();
     assert!(move_gen::is_attacked(&board5, board5.king_pos(board5.side_to_move())),
-            format!("Error: King should be under attack here at {}:\n{}",
-                    board5.king_pos(board5.side_to_move()), board5));
+            "Error: King should be under attack here at {}:\n{}",
+                    board5.king_pos(board5.side_to_move()), board5);
     board5.generate_moves(&mut legal_moves);
     assert!(legal_moves.len() == 1,
-            format!("Found {} legal moves, expected 1. Board:\n{}", legal_moves.len(), board5));
+            "Found {} legal moves, expected 1. Board:\n{}", legal_moves.len(), board5);
     legal_moves.clear();
 
     let mut board6 = ChessBoard::from_fen("8/2p5/3p4/KP5r/1R3p1k/6P1/4P3/8 b - - 0 1").unwrap();
     assert!(move_gen::is_attacked(&board6, board6.king_pos(board6.side_to_move())),
-            format!("Error: King should be under attack here:\n{}", board5));
+            "Error: King should be under attack here:\n{}", board5);
     legal_moves.clear();
 
     let mv = board6.move_from_lan("h4g4").unwrap();
@@ -152,14 +154,14 @@ fn is_pinned_to_piece_test() {
 
 fn is_pinned_prop(board : &ChessBoard, pinee_pos : Square, pinner_pos : Square, is_pinned : bool) {
     if is_pinned {
-        assert!(move_gen::is_pinned_to_piece(&board, pinee_pos, pinner_pos),
-               format!("{} should be pinned to {}, but isn't. Board:\n{}",
-                       board[pinee_pos].piece_type(), board[pinner_pos].piece_type(), board));
+        assert!(move_gen::is_pinned_to_piece(board, pinee_pos, pinner_pos),
+               "{} should be pinned to {}, but isn't. Board:\n{}",
+                       board[pinee_pos].piece_type(), board[pinner_pos].piece_type(), board);
     }
     else {
-        assert!(!move_gen::is_pinned_to_piece(&board, pinee_pos, pinner_pos),
-               format!("{} should not be pinned to {}. Board:\n{}",
-                       board[pinee_pos].piece_type(), board[pinner_pos].piece_type(), board));
+        assert!(!move_gen::is_pinned_to_piece(board, pinee_pos, pinner_pos),
+               "{} should not be pinned to {}. Board:\n{}",
+                       board[pinee_pos].piece_type(), board[pinner_pos].piece_type(), board);
     }
 }
 
@@ -188,7 +190,7 @@ fn castling_test () {
     let mut moves = vec![];
     board2.generate_moves(&mut moves);
     assert!(moves.len() == 1,
-            format!("Only 1 move should be available, board:\n{}", board2));
+            "Only 1 move should be available, board:\n{}", board2);
 
     // Positions with both castlings available
     let mut board4 = ChessBoard::from_fen(
@@ -212,7 +214,7 @@ fn castling_test () {
 fn move_is_available_prop(board : &mut ChessBoard, c_move : ChessMove) {
     let mut all_moves = vec![];
     board.generate_moves(&mut all_moves);
-    assert!(all_moves.iter().any(|mv|c_move == *mv),
+    assert!(all_moves.contains(&c_move),
             "{} should be legal here, board:{}Legal moves: {:?}",
             board.move_to_lan(&c_move), board, all_moves);
 }
@@ -220,7 +222,7 @@ fn move_is_available_prop(board : &mut ChessBoard, c_move : ChessMove) {
 fn move_is_unavailable_prop(board : &mut ChessBoard, c_move : ChessMove) {
     let mut all_moves = vec![];
     board.generate_moves(&mut all_moves);
-    assert!(!all_moves.iter().any(|mv|c_move == *mv),
+    assert!(!all_moves.contains(&c_move),
             "{} should not be legal here, board:{}Legal moves: {:?}",
             board.move_to_lan(&c_move), board, all_moves);
 }
@@ -343,4 +345,274 @@ fn correct_move_gen_test7() {
     let mut board7 = ChessBoard::from_fen(
         "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").unwrap();
     assert_eq!(tools::perft(&mut board7, 3), 62_379);
-}
\ No newline at end of file
+}
+
+#[test]
+fn bitboards_match_mailbox_test() {
+    let mut board = ChessBoard::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    for mv in moves {
+        let reverse_move = board.do_move(mv);
+        bitboards_match_mailbox_prop(&board);
+        board.reverse_move(reverse_move);
+        bitboards_match_mailbox_prop(&board);
+    }
+}
+
+fn bitboards_match_mailbox_prop(board: &ChessBoard) {
+    for square in (0..64).map(Square) {
+        let piece = board[square];
+        assert!(board.piece_type_bitboard(piece.piece_type()).get(square),
+                "{} not set in {:?} bitboard on board:{}", square, piece.piece_type(), board);
+        for &color in &[White, Black] {
+            assert_eq!(board.color_bitboard(color).get(square), piece.color() == Some(color),
+                       "{} bitboard wrong on {}, board:{}", color, square, board);
+        }
+    }
+    assert_eq!(board.occupied().popcount() + board.piece_type_bitboard(Empty).popcount(), 64);
+}
+
+#[test]
+fn generate_legal_into_test() {
+    let board = ChessBoard::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    let mut move_list = MoveList::new();
+    board.generate_legal_into(&mut move_list);
+    assert_eq!(move_list.len(), 48);
+
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(&moves[..], &move_list[..]);
+
+    move_list.clear();
+    assert!(move_list.is_empty());
+}
+
+#[test]
+fn check_info_test() {
+    // Single check from the knight
+    let board = ChessBoard::from_fen("4k3/8/3N4/8/8/8/8/4K2B b - - 0 1").unwrap();
+    let check_info = move_gen::CheckInfo::new(&board);
+    assert_eq!(check_info.checkers.popcount(), 1);
+
+    // Double check from the knight and the bishop
+    let board = ChessBoard::from_fen("4k3/2N5/8/8/B7/8/8/4K3 b - - 0 1").unwrap();
+    let check_info = move_gen::CheckInfo::new(&board);
+    assert_eq!(check_info.checkers.popcount(), 2);
+    assert!(check_info.check_mask.is_empty());
+
+    // The d7 knight is pinned by the bishop, and may not move at all
+    let board = ChessBoard::from_fen("4k3/3n4/8/1B6/8/8/8/4K3 b - - 0 1").unwrap();
+    let check_info = move_gen::CheckInfo::new(&board);
+    assert!(!check_info.is_in_check());
+    assert_eq!(check_info.pinned, BitBoard::from_square(Square::from_alg("d7").unwrap()));
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.iter().all(|mv| mv.from != Square::from_alg("d7").unwrap()));
+}
+
+#[test]
+fn en_passant_discovered_check_test() {
+    // Capturing en passant would remove both pawns from the king's rank
+    let board = ChessBoard::from_fen("8/8/8/KPp4r/8/8/8/7k w - c6 0 2").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(!moves.contains(&board.move_from_lan("b5c6").unwrap()));
+
+    // Capturing en passant resolves the check from the pawn
+    let board = ChessBoard::from_fen("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1").unwrap();
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.contains(&board.move_from_lan("e4d3").unwrap()));
+}
+
+#[test]
+fn staged_move_gen_test() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    ];
+    for fen in fens.iter() {
+        let mut board = ChessBoard::from_fen(fen).unwrap();
+        staged_move_gen_prop(&mut board, 2);
+    }
+}
+
+fn staged_move_gen_prop(board: &mut ChessBoard, depth: u16) {
+    let mut legal_moves = vec![];
+    board.generate_moves(&mut legal_moves);
+    let mut captures = MoveList::new();
+    move_gen::generate_captures(board, &mut captures);
+    let mut quiets = MoveList::new();
+    move_gen::generate_quiets(board, &mut quiets);
+
+    assert_eq!(captures.len() + quiets.len(), legal_moves.len(), "Wrong number of staged moves on board:{}", board);
+    for mv in legal_moves.iter() {
+        let is_capture = !board[mv.to].is_empty() || mv.prom.is_some()
+            || (board[mv.from].piece_type() == Pawn && mv.from.file() != mv.to.file());
+        assert_eq!(captures.contains(mv), is_capture, "{} in wrong stage on board:{}", mv, board);
+        assert_eq!(quiets.contains(mv), !is_capture, "{} in wrong stage on board:{}", mv, board);
+
+        let mut board_after = board.clone();
+        board_after.do_move(*mv);
+        let is_check = move_gen::is_attacked(&board_after, board_after.king_pos(board_after.side_to_move()));
+        assert_eq!(move_gen::gives_check(board, *mv), is_check, "Wrong check status for {} on board:{}", mv, board);
+    }
+
+    let mut quiet_checks = MoveList::new();
+    move_gen::generate_quiet_checks(board, &mut quiet_checks);
+    assert!(quiets.iter().filter(|mv| move_gen::gives_check(board, **mv)).eq(quiet_checks.iter()));
+
+    if move_gen::is_attacked(board, board.king_pos(board.side_to_move())) {
+        let mut evasions = MoveList::new();
+        move_gen::generate_evasions(board, &mut evasions);
+        assert_eq!(evasions.len(), legal_moves.len());
+        assert!(legal_moves.iter().all(|mv| evasions.contains(mv)));
+    }
+
+    if depth > 0 {
+        for mv in legal_moves {
+            let reverse_move = board.do_move(mv);
+            staged_move_gen_prop(board, depth - 1);
+            board.reverse_move(reverse_move);
+        }
+    }
+}
+
+#[test]
+fn generate_quiet_checks_test() {
+    // Every knight move uncovers the rook, and none of the rook or king moves give check
+    let board = ChessBoard::from_fen("3k4/8/8/8/3N4/8/8/3RK3 w - - 0 1").unwrap();
+    let mut quiet_checks = MoveList::new();
+    move_gen::generate_quiet_checks(&board, &mut quiet_checks);
+    let mut quiet_checks: Vec<String> = quiet_checks.iter().map(|mv| board.move_to_san(mv)).collect();
+    quiet_checks.sort();
+    assert_eq!(quiet_checks, vec!["Nb3+", "Nb5+", "Nc2+", "Nc6+", "Ne2+", "Ne6+", "Nf3+", "Nf5+"]);
+}
+
+#[test]
+fn see_test() {
+    let see = |fen, san| {
+        let board = ChessBoard::from_fen(fen).unwrap();
+        let mv = board.move_from_san(san).unwrap();
+        board.see(mv)
+    };
+    // Undefended pawn
+    assert_eq!(see("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "Rxe5"), 100);
+    // The knight is lost for a pawn, as the rook on e2 is stopped by the bishop on f6
+    assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "Nxe5"), -200);
+    // The queen behind the rook recaptures through it, but the rook is still lost for two pawns
+    assert_eq!(see("4k3/8/3p4/4p3/8/8/4R3/4QK2 w - - 0 1", "Rxe5"), -300);
+    assert_eq!(see("4k3/8/8/4p3/8/8/4R3/4QK2 w - - 0 1", "Rxe5"), 100);
+    assert_eq!(see("4k3/4r3/8/4p3/8/8/4R3/4QK2 w - - 0 1", "Rxe5"), 100);
+    // Moving the queen to a square attacked by a pawn
+    assert_eq!(see("4k3/8/8/3p4/8/8/Q7/4K3 w - - 0 1", "Qc4"), -900);
+    // Trading pawns en passant
+    assert_eq!(see("4k3/8/8/2p5/3Pp3/8/8/4K3 b - d3 0 1", "exd3"), 100);
+    assert_eq!(see("4k3/8/8/8/3Pp3/8/4K3/8 b - d3 0 1", "exd3"), 0);
+    // Promoting with a capture, and being recaptured by the king
+    assert_eq!(see("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=Q"), 1300);
+    assert_eq!(see("1rk5/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=Q+"), 500 - 100);
+    // The king cannot recapture a defended piece
+    assert_eq!(see("4k3/4n3/8/8/7B/8/8/4RK2 w - - 0 1", "Bxe7"), 300);
+}
+
+#[test]
+fn see_ge_test() {
+    let board = ChessBoard::from_fen("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1").unwrap();
+    let mv = board.move_from_san("Nxe5").unwrap();
+    assert!(board.see_ge(mv, -200));
+    assert!(!board.see_ge(mv, -199));
+    assert!(!board.see_ge(mv, 0));
+}
+
+#[test]
+fn attack_queries_test() {
+    let square = |str: &str| Square::from_alg(str).unwrap();
+    let squares = |strs: &[&str]| strs.iter().fold(BitBoard::empty(), |bitboard, str| bitboard.set(square(str)));
+
+    let board = ChessBoard::from_fen("4k3/8/3p4/4p3/8/5N2/4R3/4QK2 w - - 0 1").unwrap();
+    // The queen is behind the rook, so only the rook and knight attack e5
+    assert_eq!(board.attackers_to(square("e5"), White), squares(&["e2", "f3"]));
+    assert_eq!(board.attackers_to(square("e5"), Black), squares(&["d6"]));
+    assert_eq!(board.attackers_to(square("e7"), Black), squares(&["e8"]));
+    assert!(board.checkers().is_empty());
+
+    let black_attacks = board.attacked_squares(Black);
+    assert_eq!(black_attacks, squares(&["c5", "e5", "d4", "f4", "d8", "d7", "e7", "f7", "f8"]));
+
+    let white_attacks = board.attacked_squares(White);
+    assert!(white_attacks.get(square("e5")) && white_attacks.get(square("e2")));
+    assert!(!white_attacks.get(square("e6")));
+
+    // Double check from the knight and the bishop
+    let board = ChessBoard::from_fen("4k3/2N5/8/8/B7/8/8/4K3 b - - 0 1").unwrap();
+    assert_eq!(board.checkers(), squares(&["c7", "a4"]));
+}
+
+#[test]
+fn gives_check_test() {
+    let gives_check = |fen, lan| {
+        let board = ChessBoard::from_fen(fen).unwrap();
+        let mv = board.move_from_lan(lan).unwrap();
+        board.gives_check(mv)
+    };
+    // Promotions, where the pawn no longer blocks its promoted piece
+    assert!(gives_check("k7/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8q"));
+    assert!(gives_check("k7/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8r"));
+    assert!(!gives_check("k7/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7e8b"));
+    assert!(gives_check("8/4P3/3k4/8/8/8/8/4K3 w - - 0 1", "e7e8n"));
+    // Checks from the rook after castling
+    assert!(gives_check("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+    assert!(!gives_check("6k1/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1"));
+    // Both pawns leave the rank when capturing en passant
+    assert!(gives_check("8/8/8/R2Pp2k/8/8/8/4K3 w - e6 0 1", "d5e6"));
+    // A discovered check by the king
+    assert!(gives_check("8/8/8/8/k3K2R/8/8/8 w - - 0 1", "e4e3"));
+    assert!(!gives_check("8/8/8/8/k3K2R/8/8/8 w - - 0 1", "e4f4"));
+}
+
+#[test]
+fn san_check_suffix_test() {
+    let board = ChessBoard::from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    assert_eq!(board.move_to_san(&board.move_from_lan("e1g1").unwrap()), "0-0+");
+    assert_eq!(board.move_to_san(&board.move_from_lan("h1h8").unwrap()), "Rh8+");
+    assert_eq!(board.move_to_san(&board.move_from_lan("h1h2").unwrap()), "Rh2");
+
+    let board = ChessBoard::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    assert_eq!(board.move_to_san(&board.move_from_lan("a1a8").unwrap()), "Ra8#");
+}
+
+#[test]
+fn chess960_perft_test() {
+    for (fen, answers) in CHESS960_PERFT_POSITIONS.iter() {
+        let mut board = ChessBoard::from_fen(fen).unwrap();
+        tools::perft_check_answers(&mut board, &answers[..4]);
+    }
+}
+
+#[test]
+#[ignore]
+fn chess960_perft_test_long() {
+    for (fen, answers) in CHESS960_PERFT_POSITIONS.iter() {
+        let mut board = ChessBoard::from_fen(fen).unwrap();
+        tools::perft_check_answers(&mut board, answers);
+    }
+}
+
+/// Positions from the standard Chess960 perft suite, with castling rights in Shredder-FEN
+const CHESS960_PERFT_POSITIONS: [(&str, [u64; 6]); 7] = [
+    ("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9", [1, 21, 528, 12_189, 326_672, 8_146_062]),
+    ("2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9", [1, 21, 807, 18_002, 667_366, 16_253_601]),
+    ("1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9", [1, 28, 1_120, 31_058, 1_171_749, 34_030_312]),
+    ("qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9", [1, 29, 899, 26_578, 824_055, 24_851_983]),
+    ("q1bnrkr1/ppppp2p/2n2p2/4b1p1/2NP4/8/PPP1PPPP/QNB1RRKB w ge - 1 9", [1, 30, 860, 24_566, 732_757, 21_093_346]),
+    ("qbn1brkr/ppp1p1p1/2n4p/3p1p2/P7/6PP/QPPPPP2/1BNNBRKR w HFhf - 0 9", [1, 25, 635, 17_054, 465_806, 13_203_304]),
+    ("qn1rbbkr/ppp2p1p/1n1pp1p1/8/3P4/P6P/1PP1PPPK/QNNRBB1R w hd - 2 9", [1, 28, 811, 23_175, 679_699, 19_836_606]),
+];
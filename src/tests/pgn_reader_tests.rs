@@ -0,0 +1,156 @@
+use crate::chess_board::ChessBoard;
+use crate::pgn_reader::{PgnGame, PgnReader};
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7
+8. Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7
+14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+/// Returns the FEN fields of the position, without the move counters
+fn position_fen(board: &ChessBoard) -> String {
+    board.to_fen().split(' ').take(4).collect::<Vec<_>>().join(" ")
+}
+
+fn read_one(input: &str) -> PgnGame {
+    let mut reader = PgnReader::new(input.as_bytes());
+    let game = reader.read_game().unwrap().unwrap();
+    assert!(reader.read_game().unwrap().is_none());
+    game
+}
+
+#[test]
+fn read_game_test() {
+    let game = read_one(OPERA_GAME);
+    assert_eq!(game.tags.len(), 7);
+    assert_eq!(game.tag("White"), Some("Paul Morphy"));
+    assert_eq!(game.tag("Black"), Some("Duke Karl / Count Isouard"));
+    assert_eq!(game.tag("ECO"), None);
+    assert_eq!(game.start_board, ChessBoard::start_board());
+    assert_eq!(game.mainline.moves.len(), 33);
+    assert_eq!(game.result, Some(GameResult::WhiteWin));
+
+    let final_board = game.final_board();
+    assert_eq!(position_fen(&final_board), "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k -");
+    assert_eq!(final_board.game_result(), Some(GameResult::WhiteWin));
+}
+
+#[test]
+fn annotations_test() {
+    let game = read_one(
+        "{Opening comment} 1. e4! $14 e5?! (1... c5 {Sicilian} 2. Nf3 (2. c3) d6 ; Najdorf next\n) \
+         2. Nf3 {Main line} {Second comment} *",
+    );
+    assert!(game.tags.is_empty());
+    assert_eq!(game.result, None);
+    assert_eq!(game.mainline.comments, vec!["Opening comment".to_string()]);
+
+    let moves = &game.mainline.moves;
+    assert_eq!(moves.len(), 3);
+    assert_eq!(moves[0].nags, vec![1, 14]);
+    assert_eq!(moves[1].nags, vec![6]);
+    assert_eq!(moves[2].comments, vec!["Main line".to_string(), "Second comment".to_string()]);
+
+    assert_eq!(moves[1].variations.len(), 1);
+    let sicilian = &moves[1].variations[0];
+    assert_eq!(sicilian.moves.len(), 3);
+    assert_eq!(sicilian.moves[0].comments, vec!["Sicilian".to_string()]);
+    assert_eq!(sicilian.moves[2].comments, vec!["Najdorf next".to_string()]);
+    assert_eq!(sicilian.moves[1].variations.len(), 1);
+    let alapin = &sicilian.moves[1].variations[0];
+    assert_eq!(alapin.moves.len(), 1);
+    assert_eq!(alapin.moves[0].mv, ChessBoard::from_fen(
+        "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap().move_from_san("c3").unwrap());
+}
+
+#[test]
+fn multiline_comment_test() {
+    let game = read_one("1. d4 {A comment\n   over  two lines} d5 1/2-1/2");
+    assert_eq!(game.mainline.moves[0].comments, vec!["A comment over two lines".to_string()]);
+    assert_eq!(game.result, Some(GameResult::Draw));
+}
+
+#[test]
+fn fen_tag_test() {
+    let game = read_one(
+        "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1\"]\n\n1. O-O Kd7 2. Rfd1+ 0-1",
+    );
+    assert_eq!(game.start_board.to_fen(), "4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+    assert_eq!(position_fen(&game.final_board()), "8/3k4/8/8/8/8/8/R2R2K1 b - -");
+    assert_eq!(game.result, Some(GameResult::BlackWin));
+}
+
+#[test]
+fn result_tag_without_marker_test() {
+    let input = "[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4#\n\n[Result \"*\"]\n\n1. e4\n";
+    let games: Vec<PgnGame> = PgnReader::new(input.as_bytes()).map(Result::unwrap).collect();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].result, Some(GameResult::BlackWin));
+    assert_eq!(games[0].mainline.moves.len(), 4);
+    assert_eq!(games[1].result, None);
+    assert_eq!(games[1].mainline.moves.len(), 1);
+}
+
+#[test]
+fn escapes_test() {
+    let game = read_one("% An escaped line 1. e4\n[Event \"The \\\"Big\\\" Open \\\\ 2024\"]\n\n1. e4 *");
+    assert_eq!(game.tag("Event"), Some("The \"Big\" Open \\ 2024"));
+    assert_eq!(game.mainline.moves.len(), 1);
+}
+
+#[test]
+fn error_position_test() {
+    let input = "[Event \"First\"]\n\n1. e4 e5 *\n\n[Event \"Second\"]\n\n1. e4 e5\n2. Ke3 Nc6 *\n\n\
+                 [Event \"Third\"]\n\n1. d4 *\n";
+    let mut reader = PgnReader::new(input.as_bytes());
+    assert_eq!(reader.read_game().unwrap().unwrap().tag("Event"), Some("First"));
+
+    let err = reader.read_game().unwrap_err();
+    assert!(err.to_string().contains("Illegal move Ke3 at line 8, column 4"), "{}", err);
+
+    // Reading continues with the next game
+    let game = reader.read_game().unwrap().unwrap();
+    assert_eq!(game.tag("Event"), Some("Third"));
+    assert!(reader.read_game().unwrap().is_none());
+}
+
+#[test]
+fn illegal_castling_test() {
+    // The king would castle through the bishop's attack on f1
+    let input = "[FEN \"4k3/8/8/8/8/8/6b1/R3K2R w KQ - 0 1\"]\n\n1. O-O *";
+    let err = PgnReader::new(input.as_bytes()).read_game().unwrap_err();
+    assert!(err.to_string().contains("line 3, column 4"), "{}", err);
+}
+
+#[test]
+fn syntax_errors_test() {
+    for input in &[
+        "1. e4 (e5 *",
+        "1. e4 e5 ) *",
+        "( 1. e4 ) *",
+        "[Event \"Unterminated]\n\n1. e4 *",
+        "1. e4 {Unterminated *",
+        "1. e4 (1. d4 1-0) *",
+        "[FEN \"8/8/8 w - - 0 1\"]\n\n*",
+        "1. e4 $ *",
+        "1. e4 !!! *",
+    ] {
+        assert!(PgnReader::new(input.as_bytes()).read_game().is_err(), "Read {}", input);
+    }
+}
+
+#[test]
+fn past_fifty_move_rule_test() {
+    let game = read_one("[FEN \"4k3/8/8/8/8/8/8/R3K3 w - - 99 80\"]\n\n1. Ra2 Kd8 2. Ra3 *");
+    assert_eq!(game.mainline.moves.len(), 3);
+    assert_eq!(game.final_board().half_move_clock, 102);
+}
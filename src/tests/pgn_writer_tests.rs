@@ -0,0 +1,130 @@
+use crate::chess_board::ChessBoard;
+use crate::pgn_reader::{PgnGame, PgnMove, PgnReader};
+use crate::pgn_writer::{PgnWriter, MAX_LINE_LENGTH};
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+const OPERA_GAME: &str = r#"[Event "Paris"]
+[Site "Paris FRA"]
+[Date "1858.??.??"]
+[Round "?"]
+[White "Paul Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+[ECO "C41"]
+
+1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8.
+Nc3 c6 9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14.
+Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+"#;
+
+fn read_one(input: &str) -> PgnGame {
+    PgnReader::new(input.as_bytes()).read_game().unwrap().unwrap()
+}
+
+/// Checks that the exported game reads back to the same game. Only missing roster tags may be added
+fn assert_round_trip(game: &PgnGame) {
+    let pgn = game.to_pgn();
+    let read_game = read_one(&pgn);
+    assert_eq!(read_game.start_board, game.start_board);
+    assert_eq!(read_game.mainline, game.mainline);
+    assert_eq!(read_game.result, game.result);
+    for (name, value) in game.tags.iter().filter(|(name, _)| name != "Result") {
+        assert_eq!(read_game.tag(name), Some(value.as_str()));
+    }
+    assert_eq!(read_game.to_pgn(), pgn);
+}
+
+#[test]
+fn export_format_round_trip_test() {
+    let game = read_one(OPERA_GAME);
+    assert_eq!(game.to_pgn(), OPERA_GAME);
+    assert_eq!(read_one(&game.to_pgn()), game);
+}
+
+#[test]
+fn annotations_test() {
+    let game = read_one(
+        "{Opening comment} 1. e4! $14 e5?! (1... c5 {Sicilian} 2. Nf3 (2. c3) d6 ; Najdorf } next\n) \
+         2. Nf3 {Main line} {Second comment} *",
+    );
+    let pgn = game.to_pgn();
+    let movetext = pgn.split("\n\n").nth(1).unwrap();
+    assert_eq!(movetext,
+               "{Opening comment} 1. e4 $1 $14 e5 $6 (1... c5 {Sicilian} 2. Nf3 (2. c3) 2... d6\n\
+                ; Najdorf } next\n\
+                ) 2. Nf3 {Main line} {Second comment} *\n");
+    assert_round_trip(&game);
+}
+
+#[test]
+fn default_tags_test() {
+    let mut game = PgnGame::new(ChessBoard::start_board());
+    game.set_tag("White", "Carlsen, Magnus");
+    game.set_tag("Annotator", "\"Quoted\" \\ name");
+    game.set_tag("White", "Carlsen, M.");
+    game.result = Some(GameResult::Draw);
+    assert_eq!(game.to_pgn(), "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n\
+                               [White \"Carlsen, M.\"]\n[Black \"?\"]\n[Result \"1/2-1/2\"]\n\
+                               [Annotator \"\\\"Quoted\\\" \\\\ name\"]\n\n1/2-1/2\n");
+}
+
+#[test]
+fn move_numbers_from_fen_test() {
+    let start_board = ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K2R b KQ - 3 17").unwrap();
+    let mut game = PgnGame::new(start_board.clone());
+    let mut board = start_board;
+    for san in &["Kd7", "0-0", "Ke6"] {
+        let mv = board.move_from_san(san).unwrap();
+        game.mainline.moves.push(PgnMove::new(mv));
+        board.do_move(mv);
+    }
+    game.mainline.moves[1].comments.push("Castling".to_string());
+
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/8/R3K2R b KQ - 3 17\"]\n"), "{}", pgn);
+    assert!(pgn.ends_with("\n\n17... Kd7 18. O-O {Castling} 18... Ke6 *\n"), "{}", pgn);
+    assert_round_trip(&game);
+}
+
+#[test]
+fn line_wrapping_test() {
+    let mut game = read_one("1. Nf3 Nf6 2. Ng1 Ng8 3. Nf3 Nf6 4. Ng1 Ng8 *");
+    let mut board = game.final_board();
+    for _ in 0..10 {
+        for san in &["Nc3", "Nc6", "Nb1", "Nb8"] {
+            let mv = board.move_from_san(san).unwrap();
+            game.mainline.moves.push(PgnMove::new(mv));
+            board.do_move(mv);
+        }
+    }
+    game.mainline.moves[5].comments.push("A comment that is long enough to be wrapped over \
+        more than one line of movetext, word by word".to_string());
+
+    let pgn = game.to_pgn();
+    assert!(pgn.lines().all(|line| line.len() <= MAX_LINE_LENGTH), "{}", pgn);
+    assert!(pgn.lines().any(|line| line.len() > MAX_LINE_LENGTH - 8), "{}", pgn);
+    assert_round_trip(&game);
+}
+
+#[test]
+fn writer_round_trip_test() {
+    let games: Vec<PgnGame> = vec![
+        read_one(OPERA_GAME),
+        read_one("[Result \"0-1\"]\n\n1. f3 {Weak} e5 2. g4 (2. e4) Qh4# 0-1"),
+        read_one("[FEN \"4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1\"]\n\n1. O-O-O (1. O-O Kd7) Kf7 *"),
+    ];
+    let mut writer = PgnWriter::new(vec![]);
+    for game in games.iter() {
+        writer.write_game(game).unwrap();
+    }
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+    assert!(output.contains("1-0\n\n[Event \"?\"]"));
+
+    let read_games: Vec<PgnGame> = PgnReader::new(output.as_bytes()).map(Result::unwrap).collect();
+    assert_eq!(read_games.len(), games.len());
+    for (read_game, game) in read_games.iter().zip(games.iter()) {
+        assert_eq!(read_game.mainline, game.mainline);
+        assert_eq!(read_game.to_pgn(), game.to_pgn());
+    }
+}
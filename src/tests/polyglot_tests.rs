@@ -0,0 +1,160 @@
+use crate::chess_board::ChessBoard;
+use crate::chess_move::ChessMove;
+use crate::polyglot;
+use crate::polyglot::{BookBuilder, BookEntry, PolyglotBook};
+use crate::types::Square;
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+/// Reference keys from the Polyglot book format specification
+#[test]
+fn polyglot_key_test() {
+    let mut board = ChessBoard::start_board();
+    assert_eq!(polyglot::polyglot_key(&board), 0x463b_9618_1691_fc9c);
+
+    let expected_keys = [
+        ("e2e4", 0x823c_9b50_fd11_4196),
+        ("d7d5", 0x0756_b944_61c5_0fb0),
+        ("e4e5", 0x662f_afb9_65db_29d4),
+        ("f7f5", 0x22a4_8b5a_8e47_ff78),
+        ("e1e2", 0x652a_607c_a3f2_42c1),
+        ("e8f7", 0x00fd_d303_c946_bdd9),
+    ];
+    for &(lan, key) in expected_keys.iter() {
+        let mv = board.move_from_lan(lan).unwrap();
+        board.do_move(mv);
+        assert_eq!(polyglot::polyglot_key(&board), key, "Wrong key after {} on board:{}", lan, board);
+    }
+
+    let mut board = ChessBoard::start_board();
+    let expected_keys = [
+        ("a2a4", None),
+        ("b7b5", None),
+        ("h2h4", None),
+        ("b5b4", None),
+        ("c2c4", Some(0x3c81_23ea_7b06_7637)),
+        ("b4c3", None),
+        ("a1a3", Some(0x5c3f_9b82_9b27_9560)),
+    ];
+    for &(lan, key) in expected_keys.iter() {
+        let mv = board.move_from_lan(lan).unwrap();
+        board.do_move(mv);
+        if let Some(key) = key {
+            assert_eq!(polyglot::polyglot_key(&board), key, "Wrong key after {} on board:{}", lan, board);
+        }
+    }
+}
+
+#[test]
+fn polyglot_key_en_passant_test() {
+    // No black pawn can capture on e3, so the en passant file is not hashed
+    let board = ChessBoard::from_fen(
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+    let board_without_ep = ChessBoard::from_fen(
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+    assert_eq!(polyglot::polyglot_key(&board), polyglot::polyglot_key(&board_without_ep));
+}
+
+#[test]
+fn castling_move_encoding_test() {
+    let board = ChessBoard::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let square = |str| Square::from_alg(str).unwrap();
+    // e1h1 in Polyglot's encoding
+    let raw_move = 4 << 6 | 7;
+    assert_eq!(polyglot::decode_move(&board, raw_move),
+               Some(ChessMove::new(square("e1"), square("g1"))));
+    assert_eq!(polyglot::encode_move(&board, ChessMove::new(square("e1"), square("g1"))), raw_move);
+    assert_eq!(polyglot::encode_move(&board, ChessMove::new(square("e1"), square("c1"))), 4 << 6);
+
+    // In Chess960 mode, castling is already encoded as the king capturing its own rook
+    let mut board = board;
+    board.set_chess960(true);
+    assert_eq!(polyglot::decode_move(&board, raw_move),
+               Some(ChessMove::new(square("e1"), square("h1"))));
+    assert_eq!(polyglot::encode_move(&board, ChessMove::new(square("e1"), square("h1"))), raw_move);
+
+    // A promotion to a knight, a7a8n
+    let board = ChessBoard::from_fen("8/P6k/8/8/8/8/8/K7 w - - 0 1").unwrap();
+    let mv = board.move_from_lan("a7a8n").unwrap();
+    let raw_move = polyglot::encode_move(&board, mv);
+    assert_eq!(raw_move, 1 << 12 | 48 << 6 | 56);
+    assert_eq!(polyglot::decode_move(&board, raw_move), Some(mv));
+}
+
+#[test]
+fn read_book_test() {
+    let board = ChessBoard::start_board();
+    let key = polyglot::polyglot_key(&board);
+    let e4 = board.move_from_lan("e2e4").unwrap();
+    let d4 = board.move_from_lan("d2d4").unwrap();
+
+    let entries = [
+        BookEntry { key: key - 1, raw_move: 0, weight: 7, learn: 0 },
+        BookEntry { key, raw_move: polyglot::encode_move(&board, e4), weight: 20, learn: 0 },
+        BookEntry { key, raw_move: polyglot::encode_move(&board, d4), weight: 10, learn: 0 },
+        // An illegal move stored under the key, which must be skipped
+        BookEntry { key, raw_move: 4 << 6 | 36, weight: 5, learn: 0 },
+        BookEntry { key: key + 1, raw_move: 0, weight: 7, learn: 0 },
+    ];
+    let bytes: Vec<u8> = entries.iter().flat_map(|entry| entry.to_bytes().to_vec()).collect();
+
+    let book = PolyglotBook::from_reader(&bytes[..]).unwrap();
+    assert_eq!(book.entries().len(), 5);
+    assert_eq!(book.entries_for_key(key).len(), 3);
+
+    let book_moves = book.moves(&board);
+    assert_eq!(book_moves.len(), 2);
+    assert_eq!((book_moves[0].mv, book_moves[0].weight), (e4, 20));
+    assert_eq!((book_moves[1].mv, book_moves[1].weight), (d4, 10));
+
+    assert!(PolyglotBook::from_reader(&bytes[..15]).is_err());
+}
+
+#[test]
+fn build_book_test() {
+    let start_board = ChessBoard::start_board();
+    let mut builder = BookBuilder::new(2);
+    builder.add_game(&start_board, &["e4", "e5", "Nf3", "Nc6"], GameResult::WhiteWin).unwrap();
+    builder.add_game(&start_board, &["e4", "c5"], GameResult::Draw).unwrap();
+    builder.add_game(&start_board, &["d4", "d5"], GameResult::BlackWin).unwrap();
+    // Illegal moves reject the whole game
+    assert!(builder.add_game(&start_board, &["e4", "e5", "Ke3"], GameResult::WhiteWin).is_err());
+
+    let book = builder.build();
+    let root_moves = book.moves(&start_board);
+    // 1. d4 only lost, so it scored zero and is left out
+    assert_eq!(root_moves.len(), 1);
+    assert_eq!(root_moves[0].mv, start_board.move_from_san("e4").unwrap());
+    assert_eq!(root_moves[0].weight, 3);
+
+    let mut board = start_board.clone();
+    board.do_move(board.move_from_san("e4").unwrap());
+    let replies: Vec<(String, u16)> = book.moves(&board).iter()
+        .map(|book_move| (board.move_to_san(&book_move.mv), book_move.weight))
+        .collect();
+    assert_eq!(replies, vec![("c5".to_string(), 1)]);
+
+    // Moves beyond the ply limit are not in the book
+    board.do_move(board.move_from_san("e5").unwrap());
+    assert!(book.moves(&board).is_empty());
+
+    let mut bytes = vec![];
+    book.write(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 16 * book.entries().len());
+    assert_eq!(PolyglotBook::from_reader(&bytes[..]).unwrap(), book);
+}
+
+#[test]
+fn build_book_scales_weights_test() {
+    let start_board = ChessBoard::start_board();
+    let mut builder = BookBuilder::new(1).with_result_weights(40_000, 20_000, 0);
+    builder.add_game(&start_board, &["e4"], GameResult::WhiteWin).unwrap();
+    builder.add_game(&start_board, &["e4"], GameResult::WhiteWin).unwrap();
+    builder.add_game(&start_board, &["d4"], GameResult::Draw).unwrap();
+
+    let book_moves = builder.build().moves(&start_board);
+    assert_eq!(book_moves.len(), 2);
+    assert_eq!(book_moves[0].mv, start_board.move_from_san("e4").unwrap());
+    assert_eq!(book_moves[0].weight, u16::MAX);
+    assert_eq!(book_moves[1].weight, u16::MAX / 4);
+}
@@ -0,0 +1,63 @@
+use crate::racing_kings::RacingKingsBoard;
+use crate::tests::tools;
+use board_game_traits::board::{Board, GameResult};
+use pgn_traits::pgn::PgnBoard;
+
+#[test]
+fn racing_kings_start_position_perft_test() {
+    let mut board = RacingKingsBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 21, 421, 11_264, 296_242]);
+}
+
+#[test]
+fn racing_kings_fen_test() {
+    assert_eq!(RacingKingsBoard::start_board().to_fen(), "8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w - - 0 1");
+    assert!(RacingKingsBoard::from_fen("8/8/8/8/8/8/krbnNBRK/qrbnNBRQ w KQ - 0 1").is_err());
+    assert!(RacingKingsBoard::from_fen("8/8/8/8/8/8/krbnNBRK/qrbnNBRP w - - 0 1").is_err());
+    assert!(RacingKingsBoard::from_fen("8/8/8/8/8/8/krbnNBRQ/qrbnNBRQ w - - 0 1").is_err());
+}
+
+#[test]
+fn giving_check_is_illegal_test() {
+    let board = RacingKingsBoard::from_fen("8/8/8/8/8/8/k7/6RK w - - 0 1").unwrap();
+    assert!(board.move_from_san("Ra1").is_err());
+    assert!(board.move_from_san("Rg2").is_err());
+    assert!(board.move_from_san("Rb1").is_ok());
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.iter().all(|mv| !board.board().gives_check(*mv)));
+}
+
+#[test]
+fn race_to_eighth_rank_test() {
+    // Black cannot follow White to the eighth rank
+    let mut board = RacingKingsBoard::from_fen("8/6K1/8/8/8/8/k7/8 w - - 0 1").unwrap();
+    board.do_move(board.move_from_san("Kg8").unwrap());
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.is_empty());
+
+    // Black can equalize
+    let mut board = RacingKingsBoard::from_fen("8/k5K1/8/8/8/8/8/8 w - - 0 1").unwrap();
+    board.do_move(board.move_from_san("Kg8").unwrap());
+    assert_eq!(board.game_result(), None);
+    board.do_move(board.move_from_san("Ka8").unwrap());
+    assert_eq!(board.game_result(), Some(GameResult::Draw));
+
+    // Black may not get there, and then White wins
+    let mut board = RacingKingsBoard::from_fen("8/k5K1/8/8/8/8/8/8 w - - 0 1").unwrap();
+    board.do_move(board.move_from_san("Kg8").unwrap());
+    board.do_move(board.move_from_san("Kb6").unwrap());
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+
+    // Black reaching the eighth rank first wins immediately
+    let mut board = RacingKingsBoard::from_fen("8/k7/8/8/8/8/8/7K b - - 0 1").unwrap();
+    board.do_move(board.move_from_san("Ka8").unwrap());
+    assert_eq!(board.game_result(), Some(GameResult::BlackWin));
+}
+
+#[test]
+fn castling_is_illegal_test() {
+    assert!(RacingKingsBoard::start_board().move_from_san("O-O").is_err());
+}
@@ -0,0 +1,67 @@
+use crate::three_check::ThreeCheckBoard;
+use crate::tests::tools;
+use board_game_traits::board::{Board, GameResult};
+use board_game_traits::board::Color::{Black, White};
+use pgn_traits::pgn::PgnBoard;
+
+#[test]
+fn three_check_start_position_perft_test() {
+    let mut board = ThreeCheckBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_281]);
+}
+
+#[test]
+fn three_check_fen_test() {
+    assert_eq!(ThreeCheckBoard::start_board().to_fen(),
+               "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1");
+
+    let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2+3 0 3";
+    let board = ThreeCheckBoard::from_fen(fen).unwrap();
+    assert_eq!(board.to_fen(), fen);
+    assert_eq!(board.checks_given(White), 1);
+    assert_eq!(board.checks_remaining(Black), 3);
+
+    let board = ThreeCheckBoard::from_fen(
+        "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3 +1+2").unwrap();
+    assert_eq!(board.checks_given(White), 1);
+    assert_eq!(board.checks_given(Black), 2);
+    assert_eq!(board.to_fen(), "rnbqkbnr/ppp2ppp/8/3pp3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 2+1 0 3");
+
+    let board = ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.checks_given(White), 0);
+
+    assert!(ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 4+3 0 1").is_err());
+    assert!(ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 +1").is_err());
+}
+
+#[test]
+fn third_check_wins_test() {
+    let mut board = ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 2+3 0 1").unwrap();
+    let mv = board.move_from_san("Ra8").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Ra8+");
+    board.do_move(mv);
+    assert_eq!(board.game_result(), None);
+    assert_eq!(board.checks_given(White), 2);
+
+    let mut board = ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 1+3 0 1").unwrap();
+    let mv = board.move_from_san("Ra8").unwrap();
+    assert_eq!(board.move_to_san(&mv), "Ra8#");
+    let reverse_move = board.do_move(mv);
+    assert_eq!(board.game_result(), Some(GameResult::WhiteWin));
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert!(moves.is_empty());
+
+    board.reverse_move(reverse_move);
+    assert_eq!(board.checks_remaining(White), 1);
+    assert_eq!(board.game_result(), None);
+}
+
+#[test]
+fn three_check_material_test() {
+    // A lone bishop cannot mate, but it can give check
+    let board = ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), None);
+    let board = ThreeCheckBoard::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.game_result(), Some(GameResult::Draw));
+}
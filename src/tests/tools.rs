@@ -5,7 +5,7 @@ use board_game_traits::board::Board;
 pub fn perft<B: Board>(board : &mut B, depth : u16) -> u64
 {
     if depth == 0 { 1 }
-    else if board.game_result() != None { 0 } else {
+    else if board.game_result().is_some() { 0 } else {
         let mut moves = Vec::with_capacity(100);
         board.generate_moves(&mut moves);
         if depth == 1 { moves.len() as u64 } else {
@@ -0,0 +1,63 @@
+use crate::chess_board::ChessBoard;
+use crate::tests::tools;
+use crate::variant::{StandardBoard, Variant, VariantBoard};
+use board_game_traits::board::Board;
+use pgn_traits::pgn::PgnBoard;
+
+#[test]
+fn standard_board_perft_test() {
+    let mut board = StandardBoard::start_board();
+    tools::perft_check_answers(&mut board, &[1, 20, 400, 8_902, 197_281]);
+
+    let mut board = StandardBoard::from_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+    tools::perft_check_answers(&mut board, &[1, 48, 2_039, 97_862]);
+}
+
+#[test]
+fn standard_board_matches_chess_board_test() {
+    let mut board = StandardBoard::start_board();
+    let mut chess_board = ChessBoard::start_board();
+    for san in &["e4", "e5", "Qh5", "Nc6", "Bc4", "Nf6", "Qxf7#"] {
+        let mv = board.move_from_san(san).unwrap();
+        assert_eq!(board.move_to_san(&mv), chess_board.move_to_san(&mv));
+        board.do_move(mv);
+        chess_board.do_move(mv);
+        assert_eq!(board.to_fen(), chess_board.to_fen());
+        assert_eq!(board.game_result(), chess_board.game_result());
+    }
+}
+
+#[test]
+fn standard_board_san_suffix_test() {
+    let san = |fen, san| {
+        let board = StandardBoard::from_fen(fen).unwrap();
+        board.move_to_san(&board.move_from_san(san).unwrap())
+    };
+    // Checks that draw the game are not mate
+    assert_eq!(san("7k/8/8/6N1/8/8/8/K7 w - - 0 1", "Nf7"), "Nf7+");
+    assert_eq!(san("7k/8/8/8/8/8/6Q1/K7 w - - 99 80", "Qg7"), "Qg7+");
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80", "Ra8"), "Ra8#");
+}
+
+/// Standard chess, except that captures are compulsory
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct CompulsoryCaptures;
+
+impl Variant for CompulsoryCaptures {
+    type Undo = ();
+    const COMPULSORY_CAPTURES: bool = true;
+}
+
+#[test]
+fn custom_variant_test() {
+    let mut board = VariantBoard::<CompulsoryCaptures>::start_board();
+    for san in &["e4", "d5"] {
+        let mv = board.move_from_san(san).unwrap();
+        board.do_move(mv);
+    }
+    let mut moves = vec![];
+    board.generate_moves(&mut moves);
+    assert_eq!(moves, vec![board.move_from_san("exd5").unwrap()]);
+    assert!(board.move_from_san("Nf3").is_err());
+}
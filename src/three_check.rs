@@ -0,0 +1,144 @@
+//! Three-check, where a side also wins by giving check for the third time
+
+use crate::chess_board::{ChessBoard, Termination};
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use crate::types::PieceType::*;
+use crate::variant::{Variant, VariantBoard, VariantReverseMove};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color, GameResult};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+
+/// The number of checks that wins the game
+pub const CHECKS_TO_WIN: u8 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreeCheck {
+    // The number of checks given by each color
+    checks_given: [u8; 2],
+}
+
+pub type ThreeCheckBoard = VariantBoard<ThreeCheck>;
+
+/// Holds the number of checks given by each color before the move
+pub type ThreeCheckReverseMove = VariantReverseMove<[u8; 2]>;
+
+impl VariantBoard<ThreeCheck> {
+    /// Returns how many times `color` has given check
+    pub fn checks_given(&self, color: Color) -> u8 {
+        self.variant.checks_given[color.disc()]
+    }
+
+    /// Returns how many more checks `color` needs to win
+    pub fn checks_remaining(&self, color: Color) -> u8 {
+        CHECKS_TO_WIN.saturating_sub(self.checks_given(color))
+    }
+}
+
+/// Parses a check counter from 0 to 3
+fn parse_check_count(input: &str, fen: &str) -> Result<u8, pgn::Error> {
+    match input.parse() {
+        Ok(count) if count <= CHECKS_TO_WIN => Ok(count),
+        _ => Err(pgn::Error::new(
+            pgn::ErrorKind::ParseError,
+            format!("Invalid check counter \"{}\" in FEN {}", input, fen),
+        )),
+    }
+}
+
+impl Variant for ThreeCheck {
+    type Undo = [u8; 2];
+
+    const START_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1";
+
+    fn variant_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        if board.checks_remaining(White) == 0 {
+            Some(GameResult::WhiteWin)
+        } else if board.checks_remaining(Black) == 0 {
+            Some(GameResult::BlackWin)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a win on the third check, or the result of the game under the standard rules.
+    /// Any piece besides the king can give check, so the game is only drawn for lack of material
+    /// when just the kings are left.
+    fn game_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        if let Some(result) = Self::variant_result(board) {
+            Some(result)
+        } else if board.board.occupied() == board.board.piece_type_bitboard(King) {
+            Some(GameResult::Draw)
+        } else {
+            board
+                .board
+                .termination_with_material_rule(false)
+                .map(Termination::result)
+        }
+    }
+
+    fn do_move(board: &mut VariantBoard<Self>, mv: ChessMove) -> (ChessReverseMove, Self::Undo) {
+        let color = board.side_to_move();
+        let old_checks_given = board.variant.checks_given;
+        let reverse_move = board.board.do_move(mv);
+        if board.is_in_check() {
+            board.variant.checks_given[color.disc()] += 1;
+        }
+        (reverse_move, old_checks_given)
+    }
+
+    fn reverse_move(
+        board: &mut VariantBoard<Self>,
+        reverse_move: ChessReverseMove,
+        undo: Self::Undo,
+    ) {
+        board.board.reverse_move(reverse_move);
+        board.variant.checks_given = undo;
+    }
+
+    /// Parses a FEN string with the remaining checks as a field after the en passant square,
+    /// like `3+3`, or the checks given as a last field, like `+0+0`.
+    /// Without either, no checks have been given.
+    fn from_fen(fen: &str) -> Result<VariantBoard<Self>, pgn::Error> {
+        let mut fields: Vec<&str> = fen.split(' ').collect();
+        let mut checks_given = [0, 0];
+        if fields.len() > 4 && fields[4].get(1..).is_some_and(|rest| rest.starts_with('+')) {
+            let (white, black) = fields.remove(4).split_at(1);
+            checks_given = [
+                CHECKS_TO_WIN - parse_check_count(white, fen)?,
+                CHECKS_TO_WIN - parse_check_count(&black[1..], fen)?,
+            ];
+        } else if fields.len() > 4 && fields[fields.len() - 1].starts_with('+') {
+            let counters = fields.pop().unwrap()[1..]
+                .splitn(2, '+')
+                .collect::<Vec<_>>();
+            if counters.len() != 2 {
+                return Err(pgn::Error::new(
+                    pgn::ErrorKind::ParseError,
+                    format!("Invalid check counters in FEN {}", fen),
+                ));
+            }
+            checks_given = [
+                parse_check_count(counters[0], fen)?,
+                parse_check_count(counters[1], fen)?,
+            ];
+        }
+        Ok(VariantBoard::new(
+            ChessBoard::from_fen(&fields.join(" "))?,
+            ThreeCheck { checks_given },
+        ))
+    }
+
+    /// Writes the FEN string with the remaining checks after the en passant square, like `3+3`
+    fn to_fen(board: &VariantBoard<Self>) -> String {
+        let chess_fen = board.board.to_fen();
+        let mut fields: Vec<&str> = chess_fen.split(' ').collect();
+        let counters = format!(
+            "{}+{}",
+            board.checks_remaining(White),
+            board.checks_remaining(Black)
+        );
+        fields.insert(4, &counters);
+        fields.join(" ")
+    }
+}
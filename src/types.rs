@@ -56,7 +56,7 @@ impl PieceType {
         if disc > 6 {
             None
         } else {
-            Some(unsafe { mem::transmute(disc as u8) })
+            Some(unsafe { mem::transmute::<u8, PieceType>(disc as u8) })
         }
     }
 }
@@ -76,9 +76,10 @@ impl fmt::Display for PieceType {
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum Piece {
+    #[default]
     Empty = 0,
     WhitePawn = 2,
     BlackPawn = 3,
@@ -94,12 +95,6 @@ pub enum Piece {
     BlackKing = 13,
 }
 
-impl Default for Piece {
-    fn default() -> Self {
-        Piece::Empty
-    }
-}
-
 impl fmt::Display for Piece {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(
@@ -145,7 +140,7 @@ impl Piece {
             let abs_value = self.piece_type().value();
             match self.color().unwrap() {
                 White => abs_value,
-                Black => -1.0 * abs_value,
+                Black => -abs_value,
             }
         }
     }
@@ -156,7 +151,7 @@ impl Piece {
         match self {
             Piece::Empty => None,
             _ => {
-                if self as u32 % 2 == 0 {
+                if self as u32 & 1 == 0 {
                     Some(White)
                 } else {
                     Some(Black)
@@ -178,7 +173,7 @@ pub struct Square(pub u8);
 impl fmt::Display for Square {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         let (file, rank) = self.file_rank();
-        let actual_rank = ((rank as i8 - 8).abs() as u8 + b'0') as char;
+        let actual_rank = ((8 - rank) + b'0') as char;
 
         let _ = fmt.write_str(&format!("{}{}", (file + b'a') as char, actual_rank));
         Ok(())
@@ -194,7 +189,7 @@ impl Square {
             ))
         } else {
             let (file, rank) = (alg.chars().nth(0).unwrap(), alg.chars().nth(1).unwrap());
-            if file < 'a' || file > 'h' || rank < '1' || rank > '8' {
+            if !('a'..='h').contains(&file) || !('1'..='8').contains(&rank) {
                 Err(pgn::Error::new(
                     pgn::ErrorKind::ParseError,
                     format!("Invalid square {}", alg),
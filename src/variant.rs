@@ -0,0 +1,339 @@
+//! Chess variants as a set of rule hooks over the shared `ChessBoard` core.
+//!
+//! A variant implements `Variant`, overriding only the rules that differ from standard chess,
+//! and is played on a `VariantBoard`. Every hook defaults to the standard rules,
+//! and the defaults compile down to plain `ChessBoard` calls, so `StandardBoard` costs nothing extra.
+
+use crate::chess_board::ChessBoard;
+use crate::chess_move::{ChessMove, ChessReverseMove};
+use crate::move_gen;
+use crate::move_list::MoveList;
+use crate::types::{PieceType, PieceType::*, Square};
+use board_game_traits::board::Color::*;
+use board_game_traits::board::{Board, Color, GameResult};
+use pgn_traits::pgn;
+use pgn_traits::pgn::PgnBoard;
+use std::fmt;
+
+pub const STANDARD_START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// The rules of a chess variant. The type holds any state the variant adds to the position,
+/// such as the pieces in hand in Crazyhouse.
+///
+/// Hooks are called by `VariantBoard` in this order when generating moves:
+/// `variant_result` ends the game before any move is generated,
+/// the regular moves come from `king_safety` and `generate_castling`,
+/// then `generate_extra_moves` adds moves such as drops, `retain_legal` removes illegal moves,
+/// and finally captures are enforced if `COMPULSORY_CAPTURES` is set.
+pub trait Variant: Clone + PartialEq + Eq + Default + fmt::Debug {
+    /// What the variant needs to reverse its own changes to the position, besides the chess board's
+    type Undo: Clone + Copy + PartialEq + Eq + fmt::Debug + Default;
+
+    /// The starting position, in the variant's FEN
+    const START_FEN: &'static str = STANDARD_START_FEN;
+
+    /// Whether a side must capture when it can
+    const COMPULSORY_CAPTURES: bool = false;
+
+    /// Whether `color` must keep its king out of check. If so, its moves, including castling,
+    /// come from the standard legal move generator. Otherwise every move that follows the movement
+    /// rules of the pieces is a candidate, and castling is left to `generate_castling`.
+    fn king_safety(_board: &VariantBoard<Self>, _color: Color) -> bool {
+        true
+    }
+
+    /// Adds the castling moves of a side without king safety
+    fn generate_castling(_board: &VariantBoard<Self>, _moves: &mut Vec<ChessMove>) {}
+
+    /// Adds moves besides the regular piece moves, such as drops.
+    /// `moves` already holds the regular moves of the position
+    fn generate_extra_moves(_board: &VariantBoard<Self>, _moves: &mut Vec<ChessMove>) {}
+
+    /// Removes the candidate moves that are illegal in the variant
+    fn retain_legal(_board: &VariantBoard<Self>, _moves: &mut Vec<ChessMove>) {}
+
+    /// Returns whether the side to move is in check
+    fn is_in_check(board: &VariantBoard<Self>) -> bool {
+        !board.board.checkers().is_empty()
+    }
+
+    /// Returns the result of a win condition that ends the game whatever moves are available,
+    /// like an exploded king in Atomic. No moves are generated once it is reached.
+    fn variant_result(_board: &VariantBoard<Self>) -> Option<GameResult> {
+        None
+    }
+
+    /// Returns the result when the side to move has no legal moves. By default,
+    /// that is checkmate if the side is in check, and stalemate otherwise.
+    fn no_moves_result(board: &VariantBoard<Self>) -> GameResult {
+        match (board.is_in_check(), board.side_to_move()) {
+            (true, White) => GameResult::BlackWin,
+            (true, Black) => GameResult::WhiteWin,
+            (false, _) => GameResult::Draw,
+        }
+    }
+
+    /// Returns the result of the game, if it has ended.
+    /// By default, the move counter and repetitions are not tracked.
+    fn game_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        if let Some(result) = Self::variant_result(board) {
+            Some(result)
+        } else if board.has_legal_moves() {
+            None
+        } else {
+            Some(Self::no_moves_result(board))
+        }
+    }
+
+    /// Makes a move on the board, and returns what is needed to reverse it
+    fn do_move(board: &mut VariantBoard<Self>, mv: ChessMove) -> (ChessReverseMove, Self::Undo) {
+        (board.board.do_move(mv), Self::Undo::default())
+    }
+
+    fn reverse_move(
+        board: &mut VariantBoard<Self>,
+        reverse_move: ChessReverseMove,
+        _undo: Self::Undo,
+    ) {
+        board.board.reverse_move(reverse_move)
+    }
+
+    /// Parses a FEN string, including any of the variant's extensions
+    fn from_fen(fen: &str) -> Result<VariantBoard<Self>, pgn::Error> {
+        Ok(VariantBoard::new(
+            ChessBoard::from_fen(fen)?,
+            Self::default(),
+        ))
+    }
+
+    fn to_fen(board: &VariantBoard<Self>) -> String {
+        board.board.to_fen()
+    }
+
+    /// Returns the suffix of a move in SAN, given the position after the move.
+    /// By default, `#` for a check that loses the game for the side to move,
+    /// and `+` for any other check, including one that ends the game in a draw.
+    fn san_suffix(board: &VariantBoard<Self>) -> Option<char> {
+        if !board.is_in_check() {
+            return None;
+        }
+        match (board.game_result(), board.side_to_move()) {
+            (Some(GameResult::WhiteWin), Black) | (Some(GameResult::BlackWin), White) => Some('#'),
+            _ => Some('+'),
+        }
+    }
+}
+
+/// Standard chess, with every rule at its default
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Standard;
+
+impl Variant for Standard {
+    type Undo = ();
+
+    fn game_result(board: &VariantBoard<Self>) -> Option<GameResult> {
+        board.board.game_result()
+    }
+}
+
+pub type StandardBoard = VariantBoard<Standard>;
+
+/// A chess board that plays by the rules of the variant `V`
+#[derive(Clone, PartialEq, Eq)]
+pub struct VariantBoard<V: Variant> {
+    pub(crate) board: ChessBoard,
+    pub(crate) variant: V,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VariantReverseMove<U> {
+    reverse_move: ChessReverseMove,
+    undo: U,
+}
+
+impl<V: Variant> VariantBoard<V> {
+    pub(crate) fn new(board: ChessBoard, variant: V) -> Self {
+        VariantBoard { board, variant }
+    }
+
+    /// Returns the position on the board, without any state the variant adds
+    pub fn board(&self) -> &ChessBoard {
+        &self.board
+    }
+
+    /// Returns the variant's own state
+    pub fn variant(&self) -> &V {
+        &self.variant
+    }
+
+    /// Returns whether the side to move is in check, by the variant's rules
+    pub fn is_in_check(&self) -> bool {
+        V::is_in_check(self)
+    }
+
+    /// Returns whether the move captures a piece. Drops and castling never do
+    pub fn is_capture(&self, mv: ChessMove) -> bool {
+        if mv.is_drop() || self.board.is_castling(mv) {
+            false
+        } else {
+            self.board[mv.to].color() == Some(!self.side_to_move())
+                || (self.board[mv.from].piece_type() == Pawn
+                    && Some(mv.to) == self.board.en_passant_square())
+        }
+    }
+
+    /// Adds every move that follows the movement rules of the pieces, without castling
+    pub fn generate_pseudo_legal(&self, moves: &mut Vec<ChessMove>) {
+        let mut pseudo_legal_moves = MoveList::new();
+        move_gen::generate_pseudo_legal(&self.board, &mut pseudo_legal_moves);
+        moves.extend_from_slice(&pseudo_legal_moves);
+    }
+
+    fn has_legal_moves(&self) -> bool {
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        !moves.is_empty()
+    }
+
+    /// Parses a drop like `N@e4`, or `@e4` for a pawn, and checks that it is legal
+    fn move_from_drop(&self, input: &str) -> Result<ChessMove, pgn::Error> {
+        let input = input.trim_end_matches(&['+', '#'][..]);
+        let (piece, square) = input.split_at(input.find('@').unwrap());
+        let piece_type = match piece {
+            "" => Some(Pawn),
+            _ if piece.len() == 1 => PieceType::from_letter(piece.chars().next().unwrap()),
+            _ => None,
+        };
+        let piece_type = match piece_type {
+            Some(piece_type) if piece_type != Empty && piece_type != King => piece_type,
+            _ => {
+                return Err(pgn::Error::new(
+                    pgn::ErrorKind::ParseError,
+                    format!("Invalid piece in drop {}", input),
+                ))
+            }
+        };
+        let square = Square::from_alg(&square[1..]).map_err(|err| {
+            pgn::Error::new_caused_by(
+                pgn::ErrorKind::ParseError,
+                format!("Invalid square in drop {}", input),
+                err,
+            )
+        })?;
+        let mv = ChessMove::new_drop(square, piece_type);
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        if moves.contains(&mv) {
+            Ok(mv)
+        } else {
+            Err(pgn::Error::new(
+                pgn::ErrorKind::IllegalMove,
+                format!("{} is not a legal drop in the position", input),
+            ))
+        }
+    }
+}
+
+impl<V: Variant> Board for VariantBoard<V> {
+    type Move = ChessMove;
+    type ReverseMove = VariantReverseMove<V::Undo>;
+
+    fn start_board() -> Self {
+        V::from_fen(V::START_FEN).unwrap()
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.board.side_to_move()
+    }
+
+    fn generate_moves(&self, moves: &mut Vec<Self::Move>) {
+        if V::variant_result(self).is_some() {
+            return;
+        }
+        if V::king_safety(self, self.side_to_move()) {
+            self.board.generate_moves(moves);
+        } else {
+            self.generate_pseudo_legal(moves);
+            V::generate_castling(self, moves);
+        }
+        V::generate_extra_moves(self, moves);
+        V::retain_legal(self, moves);
+        if V::COMPULSORY_CAPTURES && moves.iter().any(|&mv| self.is_capture(mv)) {
+            moves.retain(|&mv| self.is_capture(mv));
+        }
+    }
+
+    fn do_move(&mut self, mv: Self::Move) -> Self::ReverseMove {
+        let (reverse_move, undo) = V::do_move(self, mv);
+        VariantReverseMove { reverse_move, undo }
+    }
+
+    fn reverse_move(&mut self, mv: Self::ReverseMove) {
+        V::reverse_move(self, mv.reverse_move, mv.undo)
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        V::game_result(self)
+    }
+}
+
+impl<V: Variant> PgnBoard for VariantBoard<V> {
+    fn from_fen(fen: &str) -> Result<Self, pgn::Error> {
+        V::from_fen(fen)
+    }
+
+    fn to_fen(&self) -> String {
+        V::to_fen(self)
+    }
+
+    fn move_from_san(&self, input: &str) -> Result<Self::Move, pgn::Error> {
+        if input.contains('@') {
+            return self.move_from_drop(input);
+        }
+        let mut moves = vec![];
+        self.generate_moves(&mut moves);
+        self.board.move_from_san_in(input, &moves)
+    }
+
+    fn move_to_san(&self, mv: &Self::Move) -> String {
+        let mut output = match mv.drop_piece() {
+            Some(piece_type) => format!("{}@{}", piece_type.letter(), mv.to),
+            None => {
+                let mut moves = vec![];
+                self.generate_moves(&mut moves);
+                self.board.move_to_san_in(mv, &moves)
+            }
+        };
+        let mut board = self.clone();
+        board.do_move(*mv);
+        if let Some(suffix) = V::san_suffix(&board) {
+            output.push(suffix);
+        }
+        output
+    }
+
+    fn move_from_lan(&self, input: &str) -> Result<Self::Move, pgn::Error> {
+        if input.contains('@') {
+            self.move_from_drop(input)
+        } else {
+            self.board.move_from_lan(input)
+        }
+    }
+
+    fn move_to_lan(&self, mv: &Self::Move) -> String {
+        self.board.move_to_lan(mv)
+    }
+}
+
+impl<V: Variant> fmt::Display for VariantBoard<V> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(&self.board, fmt)?;
+        writeln!(fmt, "FEN: {}", self.to_fen())
+    }
+}
+
+impl<V: Variant> fmt::Debug for VariantBoard<V> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt::Display::fmt(self, fmt)
+    }
+}
@@ -0,0 +1,102 @@
+//! Random keys for Zobrist hashing of chess positions.
+//!
+//! A position's key is the xor of the keys of every piece on its square, the side to move,
+//! the castling rights and the en passant file. The keys are generated at compile time
+//! from a fixed seed, so they are identical across runs.
+
+use crate::types::{Piece, Square};
+use board_game_traits::board::Color;
+
+struct Keys {
+    // Indexed by `Piece`. The entries for `Piece::Empty` and the unused discriminant are zero
+    pieces: [[u64; 64]; 14],
+    black_to_move: u64,
+    // Indexed by the 4 castling bits of `ChessBoard::castling_en_passant`
+    castling: [u64; 16],
+    en_passant_files: [u64; 8],
+}
+
+/// The SplitMix64 generator
+const fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys() -> Keys {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    let mut keys = Keys {
+        pieces: [[0; 64]; 14],
+        black_to_move: 0,
+        castling: [0; 16],
+        en_passant_files: [0; 8],
+    };
+    let mut piece = 2;
+    while piece < 14 {
+        let mut square = 0;
+        while square < 64 {
+            keys.pieces[piece][square] = next_random(&mut state);
+            square += 1;
+        }
+        piece += 1;
+    }
+    keys.black_to_move = next_random(&mut state);
+
+    // Each castling right has its own key, so that the combinations stay independent
+    let mut castling_rights = [0; 4];
+    let mut i = 0;
+    while i < 4 {
+        castling_rights[i] = next_random(&mut state);
+        i += 1;
+    }
+    let mut rights = 0;
+    while rights < 16 {
+        let mut i = 0;
+        while i < 4 {
+            if rights & (1 << i) != 0 {
+                keys.castling[rights] ^= castling_rights[i];
+            }
+            i += 1;
+        }
+        rights += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        keys.en_passant_files[file] = next_random(&mut state);
+        file += 1;
+    }
+    keys
+}
+
+static KEYS: Keys = generate_keys();
+
+/// Returns the key for a piece standing on the square. Zero for an empty square.
+#[inline]
+pub fn piece_key(piece: Piece, square: Square) -> u64 {
+    KEYS.pieces[piece as usize][square.0 as usize]
+}
+
+/// Returns the key for the side to move. Zero for white.
+#[inline]
+pub fn side_to_move_key(color: Color) -> u64 {
+    match color {
+        Color::White => 0,
+        Color::Black => KEYS.black_to_move,
+    }
+}
+
+/// Returns the combined key for the castling rights and en passant square,
+/// as encoded in `ChessBoard::castling_en_passant`
+#[inline]
+pub fn castling_en_passant_key(castling_en_passant: u8) -> u64 {
+    let castling_key = KEYS.castling[(castling_en_passant & 0b0000_1111) as usize];
+    if castling_en_passant & 0b1000_0000 != 0 {
+        let file = (castling_en_passant & 0b0111_0000) >> 4;
+        castling_key ^ KEYS.en_passant_files[file as usize]
+    } else {
+        castling_key
+    }
+}